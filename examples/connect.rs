@@ -9,7 +9,8 @@ use std::process::exit;
 use std::sync::mpsc::{Sender, Receiver};
 use std::sync::mpsc;
 
-// bring the trait for all common port operations into scope
+// bring the traits for all common port and client operations into scope
+use jack::JackClient;
 use jack::Port;
 
 enum Mode {
@@ -24,7 +25,7 @@ enum Mode {
 /// The callback code communicates back to the main thread via a channel and
 /// have the main thread performs actions
 struct Connector<'a> {
-    client: jack::Client<'a>,
+    client: jack::ActiveClient<'a>,
 
     /// the incoming end of the channel running on the other thread
     /// The channel can receive messages composed of an Option of a pair of port ids
@@ -32,7 +33,7 @@ struct Connector<'a> {
 }
 
 impl<'a> Connector<'a> {
-    fn new(servername: Option<String>) -> Result<Self, jack::status::Status> {
+    fn new(servername: Option<String>) -> Result<Self, String> {
         // we don't want to start a server if none is already started
         let opts   = jack::options::NO_START_SERVER;
         let myname = "connector";
@@ -41,9 +42,9 @@ impl<'a> Connector<'a> {
             Some(servername) => jack::Client::open_connection_to(myname, &*servername, opts),
         };
 
-        let client = match client {
+        let mut client = match client {
             Ok((cl, _)) => cl,
-            Err(code)   => return Err(code)
+            Err(code)   => return Err(format!("could not open client: {:?}", code))
         };
 
         // create our channel to communicate with the handler
@@ -52,22 +53,22 @@ impl<'a> Connector<'a> {
         // create the handler and give it the transmission end of the channel
         let handler = ConnectorHandler { outgoing: tx };
 
-        // create an instance of the Connector, then set up the handler
-        let mut conn = Connector { client: client, incoming: rx };
-        conn.client.set_metadata_handler(handler).unwrap();
+        // set up the handler, then activate the client
+        client.set_metadata_handler(handler).unwrap();
 
-        Ok(conn)
-    }
+        let client = match client.activate() {
+            Ok(cl)   => cl,
+            Err((_, err)) => return Err(err.to_string())
+        };
 
-    fn activate(&mut self) -> Result<(), jack::status::Status> {
-        self.client.activate()
+        Ok(Connector { client: client, incoming: rx })
     }
 
-    fn connect(&mut self, port1: &str, port2: &str) -> Result<(), jack::status::Status> {
+    fn connect(&mut self, port1: &str, port2: &str) -> Result<(), jack::Error> {
         self.client.connect_ports(port1, port2)
     }
 
-    fn disconnect(&mut self, port1: &str, port2: &str) -> Result<(), jack::status::Status> {
+    fn disconnect(&mut self, port1: &str, port2: &str) -> Result<(), jack::Error> {
         self.client.disconnect_ports(port1, port2)
     }
 
@@ -101,38 +102,28 @@ impl jack::MetadataHandler for ConnectorHandler {
         self.outgoing.send( (a, b, status) ).unwrap();
     }
 
-    fn callbacks_of_interest(&self) -> Vec<jack::MetadataHandlers> {
-        vec![jack::MetadataHandlers::PortConnect]
+    fn callbacks_of_interest(&self) -> jack::metadata_callbacks::MetadataCallbacks {
+        jack::metadata_callbacks::PORT_CONNECT
     }
 }
 
 fn do_connect(server: Option<String>, mode: Mode) {
-    // create a connector
+    // create and activate a connector
     let mut connector = match Connector::new(server) {
         Ok(conn) => conn,
-        Err(code) => {
-            println!("could not create connector: {:?}", code);
+        Err(err) => {
+            println!("could not create connector: {}", err);
             return
         }
     };
 
-    // activate the connector
-    match connector.activate() {
-        Ok(()) => (),
-        Err(code) => {
-            println!("could not activate client: {:?}", code);
-            return
-        }
-    }
-
-
     // make the connection (or disconnect some ports)
     match mode {
         Mode::Connect(p1, p2) => {
             match connector.connect(p1.as_str(), p2.as_str()) {
                 Ok(())    => (),
-                Err(code) => {
-                    println!("Connect failed because: {:?}", code);
+                Err(err)  => {
+                    println!("Connect failed because: {}", err);
                     return
                 }
             }
@@ -141,8 +132,8 @@ fn do_connect(server: Option<String>, mode: Mode) {
         Mode::Disconnect(p1, p2) => {
             match connector.disconnect(p1.as_str(), p2.as_str()) {
                 Ok(())    => (),
-                Err(code) => {
-                    println!("Disconnect failed because: {:?}", code);
+                Err(err)  => {
+                    println!("Disconnect failed because: {}", err);
                     return
                 }
             }
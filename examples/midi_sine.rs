@@ -5,12 +5,11 @@ extern crate easyjack as jack;
 // see simple_client example for some description of how this works
 extern crate nix;
 
+use jack::JackClient;
 use nix::sys::signal;
 use std::f32::consts;
 use std::f32;
 use std::sync::atomic;
-use std::sync::mpsc::{SyncSender, Receiver};
-use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 
@@ -18,14 +17,15 @@ static RUNNING: atomic::AtomicBool = atomic::ATOMIC_BOOL_INIT;
 
 type IPort = jack::InputPortHandle<jack::MidiEvent>;
 type OPort = jack::OutputPortHandle<jack::DefaultAudioSample>;
+type Freqs = [jack::DefaultAudioSample; 128];
 
 /// struct to handle metadata operations
 struct MetadataHandler {
-    outgoing: SyncSender<[jack::DefaultAudioSample; 128]>,
+    outgoing: jack::Producer<Freqs>,
 }
 
 impl MetadataHandler {
-    pub fn new(outgoing: SyncSender<[jack::DefaultAudioSample; 128]>) -> Self {
+    pub fn new(outgoing: jack::Producer<Freqs>) -> Self {
         MetadataHandler { outgoing: outgoing }
     }
 }
@@ -35,14 +35,12 @@ impl jack::MetadataHandler for MetadataHandler {
         println!("updating sample rate: {}", srate);
 
         let f = AudioHandler::calc_note_freqs(srate);
-        match self.outgoing.send(f) {
-            Ok(_) => 0,
-            Err(_) => 0
-        }
+        self.outgoing.push(f);
+        0
     }
 
-    fn callbacks_of_interest(&self) -> Vec<jack::MetadataHandlers> {
-        vec![jack::MetadataHandlers::SampleRate]
+    fn callbacks_of_interest(&self) -> jack::metadata_callbacks::MetadataCallbacks {
+        jack::metadata_callbacks::SAMPLE_RATE
     }
 }
 
@@ -55,13 +53,13 @@ struct AudioHandler {
     ramp:       jack::DefaultAudioSample,
     note_on:    jack::DefaultAudioSample,
     note:       u8,
-    note_freqs: [jack::DefaultAudioSample; 128],
+    note_freqs: Freqs,
 
-    incoming: Receiver<[jack::DefaultAudioSample; 128]>,
+    incoming: jack::Consumer<Freqs>,
 }
 
 impl AudioHandler {
-    pub fn new(input: IPort, output: OPort, incoming: Receiver<[jack::DefaultAudioSample; 128]>)
+    pub fn new(input: IPort, output: OPort, incoming: jack::Consumer<Freqs>)
         -> AudioHandler
     {
         let freqs = [0.0; 128];
@@ -76,7 +74,7 @@ impl AudioHandler {
         }
     }
 
-    pub fn calc_note_freqs(srate: jack::NumFrames) -> [jack::DefaultAudioSample; 128] {
+    pub fn calc_note_freqs(srate: jack::NumFrames) -> Freqs {
         println!("recalculating note frequencies");
         let mut freqs = [0.0; 128];
         print!("new_freqs: ");
@@ -99,30 +97,28 @@ impl jack::ProcessHandler for AudioHandler {
         let output_buffer = self.output.get_write_buffer(nframes, &ctx);
         let input_buffer  = self.input.get_read_buffer(nframes, &ctx);
 
-        let mut event_index = 0;
-        let event_count = input_buffer.len();
+        let mut events = input_buffer.iter().peekable();
 
         for i in 0..(nframes as usize) {
-            if event_index < event_count {
-                let event = input_buffer.get(event_index);
-
-                println!("evi={} evt={}, i={}", event_index, event.get_jack_time(), i);
-                if event.get_jack_time() == i as jack::NumFrames {
-                    let buf = event.raw_midi_bytes();
+            // handle every event which happens at this frame
+            while events.peek().map_or(false, |e| e.get_jack_time() == i as jack::NumFrames) {
+                let event = events.next().unwrap();
 
-                    if buf[0] & 0x90 == 0x90 {
+                match event.message() {
+                    Ok(jack::MidiMessage::NoteOn { note, .. }) => {
                         println!("note on!");
-                        self.note    = buf[1];
+                        self.note    = note;
                         self.note_on = 1.0;
-                    } else if buf[0] & 0x90 == 0x80 {
+                    },
+
+                    Ok(jack::MidiMessage::NoteOff { note, .. }) => {
                         println!("note off!");
-                        self.note    = buf[1];
+                        self.note    = note;
                         self.note_on = 0.0;
-                    }
-                    event_index += 1;
-                    if event_index < event_count {
-                        event_index += 1;
-                    }
+                    },
+
+                    // ignore everything else, including anything malformed
+                    _ => (),
                 }
             }
 
@@ -134,9 +130,9 @@ impl jack::ProcessHandler for AudioHandler {
             // println!("output_buffer[{}] = {}", i, output_buffer[i]);
         }
 
-        match self.incoming.try_recv() {
-            Ok(freqs) => self.note_freqs = freqs,
-            Err(_) => (),
+        match self.incoming.pop() {
+            Some(freqs) => self.note_freqs = freqs,
+            None        => (),
         };
 
         0
@@ -164,7 +160,7 @@ fn main() {
     let i = c.register_input_midi_port("midi_in").unwrap();
     let o = c.register_output_audio_port("audio_out").unwrap();
 
-    let (tx, rx) = mpsc::sync_channel(1);
+    let (tx, rx) = jack::RingBuffer::new(1).unwrap();
 
     let handler = AudioHandler::new(i, o, rx);
     c.set_process_handler(handler).unwrap();
@@ -172,7 +168,7 @@ fn main() {
     let handler = MetadataHandler::new(tx);
     c.set_metadata_handler(handler).unwrap();
 
-    c.activate().unwrap();
+    let c = c.activate().unwrap();
 
     while RUNNING.load(atomic::Ordering::SeqCst) {
         thread::sleep(Duration::from_millis(1000));
@@ -8,10 +8,9 @@ extern crate easyjack as jack;
 // stuttering (xruns) as the client is shutting down
 extern crate nix;
 
+use jack::{JackClient, Port};
 use nix::sys::signal;
 use std::sync::atomic;
-use std::sync::mpsc::{SyncSender, Receiver};
-use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 
@@ -24,8 +23,8 @@ static RUNNING: atomic::AtomicBool = atomic::ATOMIC_BOOL_INIT;
 
 /// This struct handles the process callback
 /// It holds a list of samples and continues to play them back until it receives a new set of
-/// samples over the `incoming` channel. When it receives new samples, it moves them (memcpy) into
-/// its own buffer.
+/// samples over the `incoming` ringbuffer. When it receives new samples, it moves them (memcpy)
+/// into its own buffer.
 /// The samples are played back at different rates so that we can hear the difference in the right
 /// and left channel
 struct AudioHandler {
@@ -42,9 +41,11 @@ struct AudioHandler {
     left_output: jack::OutputPortHandle<jack::DefaultAudioSample>,
 
     /// incoming changes
-    /// these are copied into the channel, then copied out of the channel.
+    /// these are copied into the ringbuffer, then copied out of the ringbuffer.
+    /// Unlike a channel, the ringbuffer never allocates or locks, so it is safe to use in the
+    /// process callback.
     /// Copies are pretty fast, but some applications may need to be more clever
-    incoming: Receiver<[jack::DefaultAudioSample; N]>,
+    incoming: jack::Consumer<[jack::DefaultAudioSample; N]>,
 }
 
 impl AudioHandler {
@@ -53,7 +54,7 @@ impl AudioHandler {
         init_samples: [jack::DefaultAudioSample; N],
         right: jack::OutputPortHandle<jack::DefaultAudioSample>,
         left: jack::OutputPortHandle<jack::DefaultAudioSample>,
-        incoming: Receiver<[jack::DefaultAudioSample; N]>) -> Self
+        incoming: jack::Consumer<[jack::DefaultAudioSample; N]>) -> Self
     {
         AudioHandler {
             samples: init_samples,
@@ -92,9 +93,9 @@ impl jack::ProcessHandler for AudioHandler {
         }
 
         // try to update the samples, if we need to
-        match self.incoming.try_recv() {
-            Ok(samples) => self.samples = samples,
-            Err(_) => (),
+        match self.incoming.pop() {
+            Some(samples) => self.samples = samples,
+            None          => (),
         };
 
         0
@@ -102,10 +103,10 @@ impl jack::ProcessHandler for AudioHandler {
 }
 
 /// A simple wrapper around a jack client
-/// Creates a handler and sets up channels to communicate with the handler
+/// Creates a handler and sets up a ringbuffer to communicate with the handler
 struct SimpleClient<'a> {
-    client: jack::Client<'a>,
-    sender: SyncSender<[jack::DefaultAudioSample; N]>,
+    client: jack::ActiveClient<'a>,
+    sender: jack::Producer<[jack::DefaultAudioSample; N]>,
 }
 
 impl<'a> SimpleClient<'a> {
@@ -120,32 +121,35 @@ impl<'a> SimpleClient<'a> {
         let right = client.register_output_audio_port("output1").unwrap();
         let left  = client.register_output_audio_port("output2").unwrap();
 
-        // create a channel pair we can use to communicate with
-        let (tx, rx) = mpsc::sync_channel(1);
+        // create a ringbuffer pair we can use to communicate with
+        let (tx, rx) = jack::RingBuffer::new(1).unwrap();
+
+        // the handler takes the ports, keep something to connect them with
+        let outputs = [left.as_unknown(), right.as_unknown()];
 
         // create a client, set it up as an audio processing handler
         let handler = AudioHandler::new(SimpleClient::compute_sine(0.2), right, left, rx);
         client.set_process_handler(handler).unwrap();
 
+        // start everything up, the handler cannot be replaced after this point
+        let mut client = client.activate().unwrap();
+
+        // send the output to the speakers
+        client.auto_connect(&outputs).unwrap();
+
         Ok(SimpleClient {
             client: client,
             sender: tx,
         })
     }
 
-    fn activate(&mut self) -> Result<(), jack::status::Status> {
-        self.client.activate()
-    }
-
     fn run(mut self) {
         let mut i = 0;
         while RUNNING.load(atomic::Ordering::SeqCst) {
             let newsine = SimpleClient::compute_sine(i as f32 / 10.0);
 
-            match self.sender.send(newsine) {
-                Ok(_)  => (),
-                Err(_) => (),
-            };
+            // if the handler hasn't picked up the last update yet, skip this one
+            self.sender.push(newsine);
 
             i += 1;
             if i > 10 {
@@ -186,7 +190,6 @@ fn main() {
     // set our global atomic to true
     RUNNING.store(true, atomic::Ordering::SeqCst);
 
-    let mut c = SimpleClient::new().unwrap();
-    c.activate().unwrap();
+    let c = SimpleClient::new().unwrap();
     c.run()
 }
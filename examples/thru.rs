@@ -1,6 +1,7 @@
 extern crate easyjack as jack;
 extern crate nix;
 
+use jack::{JackClient, Port};
 use nix::sys::signal;
 use std::sync::atomic;
 use std::thread;
@@ -70,11 +71,18 @@ fn main() {
     let output1 = jack_client.register_output_audio_port("output1").unwrap();
     let output2 = jack_client.register_output_audio_port("output2").unwrap();
 
+    // the handler takes the output ports, keep something to connect them with
+    let outputs = [output1.as_unknown(), output2.as_unknown()];
+
     let handler = Connector::new(vec![input1, input2], vec![output1, output2]);
     jack_client.set_process_handler(handler).unwrap();
 
     // start everything up
-    jack_client.activate().unwrap();
+    let mut jack_client = jack_client.activate().unwrap();
+
+    // pass the audio interface's input straight through to its output
+    jack_client.auto_connect(&[input1, input2]).unwrap();
+    jack_client.auto_connect(&outputs).unwrap();
 
     // wait to get a SIGINT
     // jack will do all of its magic in other threads
@@ -83,8 +91,7 @@ fn main() {
     }
 
     // now we can clean everything up
-    // the library doesn't handle this for us because it would be rather confusing, especially
-    // given how the underlying jack api actually works
+    // dropping the client would also close it, but closing it explicitly lets us see any errors
     println!("tearing down");
 
     // closing the client unregisters all of the ports
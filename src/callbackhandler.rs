@@ -3,15 +3,99 @@
 //! marker constraints because the client always takes ownership of the callback Handlers, ensuring
 //! that the callbacks will only be called in a thread safe manner
 
+use jack_sys;
+use std::mem;
+
+use transport::*;
 use types::*;
 
 /// the CallbackContext is passed to some callback handlers and used by some methods to maintain
 /// some context and control lifetimes during callbacks
-pub struct CallbackContext {}
+pub struct CallbackContext {
+    c_client:     *mut jack_sys::jack_client_t,
+    freewheeling: bool,
+}
 
 impl CallbackContext {
     #[doc(hidden)]
-    pub fn new() -> Self { CallbackContext { } }
+    pub unsafe fn new(c_client: *mut jack_sys::jack_client_t, freewheeling: bool) -> Self {
+        CallbackContext { c_client: c_client, freewheeling: freewheeling }
+    }
+
+    #[doc(hidden)]
+    pub unsafe fn get_raw(&self) -> *mut jack_sys::jack_client_t { self.c_client }
+
+    /// Returns the frame time at the start of the current process cycle. Add the index of a frame
+    /// in the buffer to get the frame time of that frame
+    pub fn last_frame_time(&self) -> NumFrames {
+        unsafe { jack_sys::jack_last_frame_time(self.c_client) }
+    }
+
+    /// Returns an estimate of the number of frames which have passed since the start of the
+    /// current process cycle
+    pub fn frames_since_cycle_start(&self) -> NumFrames {
+        unsafe { jack_sys::jack_frames_since_cycle_start(self.c_client) }
+    }
+
+    /// Returns jack's timing information for the current process cycle, or None if it is not
+    /// available (older versions of jack do not provide it)
+    pub fn cycle_times(&self) -> Option<CycleTimes> {
+        let get_cycle_times = match *jack_sys::jack_get_cycle_times {
+            Some(f) => f,
+            None    => return None,
+        };
+
+        let mut times = CycleTimes {
+            current_frames: 0,
+            current_usecs:  0,
+            next_usecs:     0,
+            period_usecs:   0.0,
+        };
+
+        let ret = unsafe {
+            get_cycle_times(
+                self.c_client,
+                &mut times.current_frames,
+                &mut times.current_usecs,
+                &mut times.next_usecs,
+                &mut times.period_usecs)
+        };
+
+        if ret == 0 { Some(times) } else { None }
+    }
+
+    /// Returns the current state and position of the transport. This is safe to call from the
+    /// process callback
+    pub fn transport_query(&self) -> (TransportState, Position) {
+        unsafe {
+            let mut raw = mem::zeroed();
+            let state = jack_sys::jack_transport_query(self.c_client, &mut raw);
+            (TransportState::from_raw(state), Position::from_raw(&raw))
+        }
+    }
+
+    /// Returns true if the server is in freewheel mode, meaning that the process callback is
+    /// being run as fast as possible instead of in real time. Wall-clock time has no relation to
+    /// the audio in this mode.
+    pub fn is_freewheeling(&self) -> bool { self.freewheeling }
+}
+
+/// Timing information for a process cycle, as estimated by jack's delay locked loop.
+/// All times are in microseconds, and comparable with `JackClient::get_time`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CycleTimes {
+    /// the frame time at the start of the cycle
+    pub current_frames: NumFrames,
+
+    /// the time at the start of the cycle
+    pub current_usecs: Time,
+
+    /// the estimated time at the start of the next cycle
+    pub next_usecs: Time,
+
+    /// the estimated length of a cycle. This may differ from the nominal period, because the
+    /// audio interface's clock drifts from the system clock
+    pub period_usecs: f32,
 }
 
 /// This trait defines a handler for the process callback
@@ -19,29 +103,111 @@ pub trait ProcessHandler {
     fn process(&mut self, ctx: &CallbackContext, nframes: NumFrames) -> i32;
 }
 
+/// This trait defines a handler which is run once in each thread jack creates for the client,
+/// before that thread starts calling any of the other handlers. This is the place to set
+/// per-thread state, such as floating point flags, thread names, or thread local allocators.
+///
+/// Unlike the other handlers, this one may be called from more than one thread at a time, so it
+/// only gets a shared reference to itself and must be `Sync`.
+pub trait ThreadInitHandler: Sync {
+    fn thread_init(&self);
+}
+
+/// This trait defines a handler for the timebase callback, which is used by the timebase master to
+/// provide bar, beat and tick information (tempo, meter, etc) for every client connected to the
+/// server.
+///
+/// The callback is run in the process thread, after the process callback, so it must obey the
+/// same real time constraints as the `ProcessHandler`.
+pub trait TimebaseHandler {
+    /// Fill in `pos.bbt` for the position the transport will be at in the next cycle.
+    ///
+    /// `pos.frame` and the other server-provided fields are ignored if they are changed.
+    /// `new_pos` is true if the position was just changed (for instance after the transport was
+    /// located somewhere else) or this is the first call after becoming timebase master. In this
+    /// case the handler cannot rely on the previous position and must compute the bar, beat and
+    /// tick from `pos.frame` alone.
+    /// `nframes` is the buffer size of the current cycle.
+    fn update_position(
+        &mut self,
+        state: TransportState,
+        nframes: NumFrames,
+        pos: &mut Position,
+        new_pos: bool);
+}
+
+/// This trait defines a handler for the sync callback, which lets a "slow-sync" client hold back
+/// the transport until it is ready to play from a new position (for instance, until it has read
+/// enough audio from disk).
+///
+/// The callback is run in the process thread, before the process callback, so it must obey the
+/// same real time constraints as the `ProcessHandler`.
+pub trait SyncHandler {
+    /// Called whenever the transport is starting, or is repositioned while it is starting or
+    /// rolling. Return true once the client is ready to roll at `pos`.
+    ///
+    /// While any slow-sync client returns false, the transport waits in
+    /// `TransportState::Starting` and this is called again every cycle, until every client is
+    /// ready or the sync timeout (see `JackClient::set_sync_timeout`) expires
+    fn ready_to_roll(&mut self, state: TransportState, pos: &Position) -> bool;
+}
+
 /// This trait defines the callbacks which may be delivered to the metadata thread
 pub trait MetadataHandler {
     /// Called when the sample rate is changed
     #[allow(unused_variables)]
     fn sample_rate_changed(&mut self, srate: NumFrames) -> i32 { 0 }
 
+    /// Called when the size of the buffers passed to the process callback is about to change.
+    /// This is called before the process callback sees the new `nframes`, so it is the place to
+    /// make sure any scratch space is large enough.
+    /// Return 0 on success, anything else is treated as an error by jack.
+    #[allow(unused_variables)]
+    fn buffer_size_changed(&mut self, nframes: NumFrames) -> i32 { 0 }
+
     /// Called when ports are connected
     #[allow(unused_variables)]
     fn on_port_connect(&mut self, a: PortId, b: PortId, status: PortConnectStatus) { }
 
-    /// Function must return all the types of callbacks it wishes to be given
-    fn callbacks_of_interest(&self) -> Vec<MetadataHandlers>;
-}
-
-pub enum MetadataHandlers {
-    SampleRate,
-    PortConnect,
-    Shutdown,
-    Freewheel,
-    BufferSize,
-    ClientRegistration,
-    PortRegistration,
-    PortRename,
-    GraphOrder,
-    Xrun,
+    /// Called when a client is registered with (`registered == true`) or unregistered from the
+    /// jack server. `name` is the name of the client
+    #[allow(unused_variables)]
+    fn on_client_registration(&mut self, name: &str, registered: bool) { }
+
+    /// Called when a port is registered (`registered == true`) or unregistered by any client,
+    /// including this one. Use `get_port_by_id` to look up the port
+    #[allow(unused_variables)]
+    fn on_port_registration(&mut self, port: PortId, registered: bool) { }
+
+    /// Called when a port is renamed. `old_name` and `new_name` are full port names
+    #[allow(unused_variables)]
+    fn on_port_rename(&mut self, port: PortId, old_name: &str, new_name: &str) { }
+
+    /// Called when the server enters (`starting == true`) or leaves freewheel mode. In freewheel
+    /// mode, the process callback is run as fast as possible instead of in real time.
+    #[allow(unused_variables)]
+    fn freewheel_changed(&mut self, starting: bool) { }
+
+    /// Called when the processing graph is reordered, for instance after ports are connected or
+    /// disconnected.
+    /// Return 0 on success, anything else is treated as an error by jack.
+    fn graph_order_changed(&mut self) -> i32 { 0 }
+
+    /// Called when the jack server shuts down, or when jack decides to disconnect the client.
+    /// `status` describes the reason for the shutdown and `reason` is jack's description of it.
+    ///
+    /// The client is no longer usable after this is called. This is called from a thread created
+    /// by jack, so it must not call any jack functions. Set a flag or send a message to another
+    /// thread instead, and close the client from there.
+    #[allow(unused_variables)]
+    fn on_shutdown(&mut self, status: status::Status, reason: &str) { }
+
+    /// Called when an xrun (a buffer underrun or overrun) occurs anywhere in the jack graph.
+    /// The client's `XrunMonitor` has already been updated when this is called.
+    /// Return 0 on success, anything else is treated as an error by jack.
+    fn on_xrun(&mut self) -> i32 { 0 }
+
+    /// Function must return all the types of callbacks it wishes to be given, as a set of flags
+    /// from the `metadata_callbacks` module
+    fn callbacks_of_interest(&self) -> metadata_callbacks::MetadataCallbacks;
 }
@@ -2,105 +2,215 @@ use jack_sys;
 use libc;
 
 use std::ffi::{CString, CStr};
+use std::fmt;
+use std::mem;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use callbackhandler::*;
+use error::*;
 use midi::*;
 use port::*;
+use transport::*;
 use types::*;
 
-/// A jack client connected to a jack server
+/// The operations which are valid for any jack client, whether or not it has been activated.
 ///
-/// TODO example
-pub struct Client<'a> {
-    c_client: *mut jack_sys::jack_client_t,
+/// Both `Client` and `ActiveClient` implement this trait, so it must be in scope to use any of
+/// these methods (`use easyjack::JackClient`). The trait is sealed, because its methods trust
+/// `get_raw` to return a valid, open client.
+pub trait JackClient: sealed::Sealed {
+    #[doc(hidden)]
+    unsafe fn get_raw(&self) -> *mut jack_sys::jack_client_t;
 
-    // store the handlers in a box so that we can store a trait object + take ownership
-    // I do not like boxing everything up because it causes unnecessary heap allocation :(
-    process_handler:  Option<Box<ProcessHandler + 'a>>,
-    metadata_handler: Option<Box<MetadataHandler + 'a>>
-}
+    /// Returns the actual name of the client. This is useful when
+    /// USE_EXACT_NAME is not specified, because the jack server might assign
+    /// some other name to your client to ensure that it is unique.
+    ///
+    /// Returns a copy of the actual string returned the JACK C API
+    fn get_name(&self) -> String {
+        // use jack's getters and setters because the names are subject to change
+        // do not need to free the string
+        unsafe {
+            let raw = self.get_raw();
+            let cstr = jack_sys::jack_get_client_name(raw);
+            String::from(CStr::from_ptr(cstr).to_str().unwrap())
+        }
+    }
 
-impl<'a> Client<'a> {
-    fn open_helper(cl: *mut jack_sys::jack_client_t, status: u32, name: &str)
-        -> Result<(Self, String), status::Status>
-    {
-        let status = status::Status::from_bits(status).unwrap();
-        if cl.is_null() {
-            Err(status)
-        } else {
-            let cl = Client {
-                c_client:          cl,
-                process_handler:   None,
-                metadata_handler:  None,
-            };
+    /// Returns the current size of the buffers passed to the process callback, in frames
+    fn buffer_size(&self) -> NumFrames {
+        unsafe { jack_sys::jack_get_buffer_size(self.get_raw()) }
+    }
 
-            let name = if status.contains(status::NAME_NOT_UNIQUE) {
-                cl.get_name()
-            } else {
-                name.to_string()
-            };
+    /// Returns a snapshot of the server's sample rate, load, scheduling and buffer size
+    fn server_info(&self) -> ServerInfo {
+        unsafe {
+            let raw = self.get_raw();
+            ServerInfo {
+                sample_rate: jack_sys::jack_get_sample_rate(raw) as NumFrames,
+                cpu_load:    jack_sys::jack_cpu_load(raw),
+                realtime:    jack_sys::jack_is_realtime(raw) != 0,
+                buffer_size: jack_sys::jack_get_buffer_size(raw),
+            }
+        }
+    }
 
-            Ok( (cl, name) )
+    /// Asks the server to change the size of the buffers passed to the process callback of every
+    /// client. This is an expensive operation and will cause a gap in the audio.
+    fn set_buffer_size(&mut self, nframes: NumFrames) -> Result<(), Error> {
+        let ret = unsafe { jack_sys::jack_set_buffer_size(self.get_raw(), nframes) };
+
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(Error::SetBufferSizeFailed {
+                client:  self.get_name(),
+                nframes: nframes,
+                code:    ret,
+            })
         }
     }
 
-    /// Creates a new client and connects it to the default jack server. The
-    /// client will use the name given. If the name is not unique, the behavior
-    /// depends on the options provided via `opts`.
-    ///
-    /// If the option to force a unique name is given (USE_EXACT_NAME) and the exact name can not
-    /// be given, Err will be returned. Otherwise Returns the client and the name assigned to the
-    /// client.
-    ///
-    /// TODO client_name_size details in docs and in code
-    pub fn open(name: &str, opts: options::Options) -> Result<(Self, String), status::Status> {
-        // TODO does jack check if the options are valid?
-        // TODO does jack check if the name is too large?
+    /// Returns the delay, in microseconds, which caused the most recent xrun
+    fn xrun_delayed_usecs(&self) -> f32 {
+        unsafe { jack_sys::jack_get_xrun_delayed_usecs(self.get_raw()) }
+    }
 
-        let cstr       = CString::new(name).unwrap();
-        let mut status = 0 as jack_sys::jack_status_t;
-        let statusptr  = &mut status as *mut jack_sys::jack_status_t;
+    /// Returns the maximum delay, in microseconds, of any process cycle since the client was
+    /// activated or since `reset_max_delayed_usecs` was called
+    fn max_delayed_usecs(&self) -> f32 {
+        unsafe { jack_sys::jack_get_max_delayed_usecs(self.get_raw()) }
+    }
 
-        let cl = unsafe { jack_sys::jack_client_open(cstr.as_ptr(), opts.bits(), statusptr) };
-        Client::open_helper(cl, status, name)
+    /// Resets the maximum delay reported by `max_delayed_usecs`
+    fn reset_max_delayed_usecs(&mut self) {
+        unsafe { jack_sys::jack_reset_max_delayed_usecs(self.get_raw()) }
     }
 
-    /// Attempts to open a client connecting to a server with a specified name
-    pub fn open_connection_to(
-        clientname: &str,
-        servername: &str,
-        opts: options::Options)
-        -> Result<(Self, String), status::Status>
-    {
-        let cstr       = CString::new(clientname).unwrap();
-        let sstr       = CString::new(servername).unwrap();
-        let mut status = 0 as jack_sys::jack_status_t;
-        let statusptr  = &mut status as *mut jack_sys::jack_status_t;
+    /// Puts the server into (`enabled == true`) or takes it out of freewheel mode. In freewheel
+    /// mode the server stops waiting on the audio interface and runs the process callbacks of
+    /// all clients as fast as possible, which is useful for offline rendering.
+    fn set_freewheel(&mut self, enabled: bool) -> Result<(), Error> {
+        let ret = unsafe { jack_sys::jack_set_freewheel(self.get_raw(), enabled as libc::c_int) };
 
-        let additionalopts = options::Options::from_bits(jack_sys::JackServerName).unwrap();
-        let cl = unsafe {
-            jack_sys::jack_client_open(
-                cstr.as_ptr(),
-                (opts | additionalopts).bits(),
-                statusptr,
-                sstr.as_ptr())
-        };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(Error::SetFreewheelFailed {
+                client:  self.get_name(),
+                enabled: enabled,
+                code:    ret,
+            })
+        }
+    }
 
-        Client::open_helper(cl, status, clientname)
+    /// Returns an estimate of the current frame time. This is a running count of frames since
+    /// the server started, which is useful for timestamping events outside of the process
+    /// callback. Use `CallbackContext::last_frame_time` inside the process callback
+    fn frame_time(&self) -> NumFrames {
+        unsafe { jack_sys::jack_frame_time(self.get_raw()) }
     }
 
-    /// Returns the actual name of the client. This is useful when
-    /// USE_EXACT_NAME is not specified, because the jack server might assign
-    /// some other name to your client to ensure that it is unique.
-    ///
-    /// Returns a copy of the actual string returned the JACK C API
-    pub fn get_name(&self) -> String {
-        // use jack's getters and setters because the names are subject to change
-        // do not need to free the string
+    /// Returns the estimated time, in microseconds, at which the given frame time occurs
+    fn frames_to_time(&self, frames: NumFrames) -> Time {
+        unsafe { jack_sys::jack_frames_to_time(self.get_raw(), frames) }
+    }
+
+    /// Returns the estimated frame time at the given time, in microseconds
+    fn time_to_frames(&self, usecs: Time) -> NumFrames {
+        unsafe { jack_sys::jack_time_to_frames(self.get_raw(), usecs) }
+    }
+
+    /// Returns jack's current system time, in microseconds. This is the clock used by all of
+    /// jack's timing functions
+    fn get_time(&self) -> Time {
+        unsafe { jack_sys::jack_get_time() }
+    }
+
+    /// Returns the current state and position of the transport.
+    /// Use `CallbackContext::transport_query` in the process callback
+    fn transport_query(&self) -> (TransportState, Position) {
         unsafe {
-            let raw = self.c_client;
-            let cstr = jack_sys::jack_get_client_name(raw);
-            String::from(CStr::from_ptr(cstr).to_str().unwrap())
+            let mut raw = mem::zeroed();
+            let state = jack_sys::jack_transport_query(self.get_raw(), &mut raw);
+            (TransportState::from_raw(state), Position::from_raw(&raw))
+        }
+    }
+
+    /// Starts the transport rolling. Any client can start or stop the transport
+    fn transport_start(&mut self) {
+        unsafe { jack_sys::jack_transport_start(self.get_raw()) }
+    }
+
+    /// Stops the transport
+    fn transport_stop(&mut self) {
+        unsafe { jack_sys::jack_transport_stop(self.get_raw()) }
+    }
+
+    /// Asks the server to move the transport to a new frame. The new position takes effect in
+    /// the next process cycle (or later, if some clients need to sync first)
+    fn transport_locate(&mut self, frame: NumFrames) -> Result<(), Error> {
+        let ret = unsafe { jack_sys::jack_transport_locate(self.get_raw(), frame) };
+
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(Error::TransportLocateFailed {
+                client: self.get_name(),
+                frame:  frame,
+                code:   ret,
+            })
+        }
+    }
+
+    /// Asks the server to move the transport to a new position, including any bar, beat and
+    /// tick information. Like `transport_locate`, the new position takes effect in a later cycle
+    fn transport_reposition(&mut self, pos: Position) -> Result<(), Error> {
+        let raw = pos.to_raw();
+        let ret = unsafe { jack_sys::jack_transport_reposition(self.get_raw(), &raw) };
+
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(Error::TransportRepositionFailed {
+                client: self.get_name(),
+                frame:  pos.frame,
+                code:   ret,
+            })
+        }
+    }
+
+    /// Stops being the timebase master. The timebase handler will not be called again, and other
+    /// clients will no longer see bar, beat and tick information until a new master takes over.
+    /// Returns an error if this client is not the timebase master
+    fn release_timebase(&mut self) -> Result<(), Error> {
+        let ret = unsafe { jack_sys::jack_release_timebase(self.get_raw()) };
+
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(Error::ReleaseTimebaseFailed {
+                client: self.get_name(),
+                code:   ret,
+            })
+        }
+    }
+
+    /// Sets how long the transport waits for slow-sync clients before it starts rolling anyway.
+    /// The default is two seconds
+    fn set_sync_timeout(&mut self, usecs: Time) -> Result<(), Error> {
+        let ret = unsafe { jack_sys::jack_set_sync_timeout(self.get_raw(), usecs) };
+
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(Error::SetSyncTimeoutFailed {
+                client: self.get_name(),
+                usecs:  usecs,
+                code:   ret,
+            })
         }
     }
 
@@ -111,121 +221,263 @@ impl<'a> Client<'a> {
     /// The port's full name contains the name of the client, followed by a colon (:), followed by
     /// the port's short name.
     ///
-    /// All ports have a type. The `port_type` module contains port types which may be used.
+    /// All ports have a type. The `port_type` module contains the types jack provides, but any
+    /// other type string may be used to create ports which only connect to ports of the same
+    /// type. `buffer_size` is the size, in bytes, of the buffers of a custom type. It is ignored
+    /// for jack's own types.
     ///
     /// You may also specify a number of flags from the `port_flags` module which control the
-    /// behavior of the created port (input vs output, etc)
+    /// behavior of the created port (input vs output, physical, terminal, etc).
     ///
-    /// This function has to figure out what kind of port to return based on the flags provided.
+    /// Because the type and direction are only known at runtime, this returns an
+    /// `UnknownPortHandle`. Use `register_input_port` or `register_output_port` to get a typed
+    /// handle for one of jack's own types. The data of a custom type can be reached with
+    /// `UnknownPortHandle::get_raw_buffer`.
     ///
-    /// TODO something about buffer size I haven't figured out yet
     /// TODO port_name_size()
     fn register_port(
         &mut self,
         name: &str,
-        ptype: PortType,
-        opts: port_flags::PortFlags)
-        -> Result<UnknownPortHandle, status::Status>
+        ptype: &str,
+        opts: port_flags::PortFlags,
+        buffer_size: usize)
+        -> Result<UnknownPortHandle, Error>
     {
         let cstr = CString::new(name).unwrap();
         let typestr = CString::new(ptype).unwrap();
 
         let port = unsafe {
             jack_sys::jack_port_register(
-                self.c_client,
+                self.get_raw(),
                 cstr.as_ptr(),
                 typestr.as_ptr(),
                 opts.bits() as ::libc::c_ulong,
-                0)
+                buffer_size as ::libc::c_ulong)
         };
 
         if port.is_null() {
             // no error code is returned from jack here
-            Err(status::FAILURE)
+            Err(Error::PortRegistrationFailed {
+                client:    self.get_name(),
+                port:      name.to_string(),
+                port_type: ptype.to_string(),
+            })
         } else {
-            Ok(UnknownPortHandle::new(port))
+            Ok(unsafe { UnknownPortHandle::new(port) })
         }
     }
 
-    /// Helper function which registers an input audio port with a given name.
-    pub fn register_input_audio_port(&mut self, name: &str)
-            -> Result<InputPortHandle<DefaultAudioSample>, status::Status>
+    /// Registers an input port carrying `K` (`DefaultAudioSample` or `MidiEvent`).
+    /// `opts` may contain any additional flags, such as `port_flags::PORT_IS_TERMINAL`.
+    /// The port is always registered as an input port
+    fn register_input_port<K: PortKind>(&mut self, name: &str, opts: port_flags::PortFlags)
+        -> Result<InputPortHandle<K>, Error>
     {
-        let p = self.register_port(
-            name,
-            port_type::DEFAULT_AUDIO_TYPE,
-            port_flags::PORT_IS_INPUT);
+        let opts = (opts - port_flags::PORT_IS_OUTPUT) | port_flags::PORT_IS_INPUT;
+        let p = self.register_port(name, K::port_type(), opts, 0);
+
+        p.map(|p| unsafe { p.force_as_input::<K>() })
+    }
 
-        p.map(|p| unsafe { p.force_as_input::<DefaultAudioSample>() })
+    /// Registers an output port carrying `K` (`DefaultAudioSample` or `MidiEvent`).
+    /// `opts` may contain any additional flags, such as `port_flags::PORT_IS_TERMINAL`.
+    /// The port is always registered as an output port
+    fn register_output_port<K: PortKind>(&mut self, name: &str, opts: port_flags::PortFlags)
+        -> Result<OutputPortHandle<K>, Error>
+    {
+        let opts = (opts - port_flags::PORT_IS_INPUT) | port_flags::PORT_IS_OUTPUT;
+        let p = self.register_port(name, K::port_type(), opts, 0);
+
+        p.map(|p| unsafe { p.force_as_output::<K>() })
+    }
+
+    /// Helper function which registers an input audio port with a given name.
+    fn register_input_audio_port(&mut self, name: &str)
+            -> Result<InputPortHandle<DefaultAudioSample>, Error>
+    {
+        self.register_input_port(name, port_flags::PortFlags::empty())
     }
 
     /// Helper function which registers an input midi port with a given name.
-    pub fn register_input_midi_port(&mut self, name: &str)
-            -> Result<InputPortHandle<MidiEvent>, status::Status>
+    fn register_input_midi_port(&mut self, name: &str)
+            -> Result<InputPortHandle<MidiEvent>, Error>
     {
-        let p = self.register_port(
-            name,
-            port_type::DEFAULT_MIDI_TYPE,
-            port_flags::PORT_IS_INPUT);
+        self.register_input_port(name, port_flags::PortFlags::empty())
+    }
 
-        p.map(|p| unsafe { p.force_as_input::<MidiEvent>() })
+    /// Helper function which registers an output midi port with a given name.
+    fn register_output_midi_port(&mut self, name: &str)
+            -> Result<OutputPortHandle<MidiEvent>, Error>
+    {
+        self.register_output_port(name, port_flags::PortFlags::empty())
     }
 
     /// Helper function which registers an output audio port with a given name.
-    pub fn register_output_audio_port(&mut self, name: &str)
-            -> Result<OutputPortHandle<DefaultAudioSample>, status::Status>
+    fn register_output_audio_port(&mut self, name: &str)
+            -> Result<OutputPortHandle<DefaultAudioSample>, Error>
     {
-        let p = self.register_port(
-            name,
-            port_type::DEFAULT_AUDIO_TYPE,
-            port_flags::PORT_IS_OUTPUT);
+        self.register_output_port(name, port_flags::PortFlags::empty())
+    }
+
+    /// Returns the size, in bytes, of the buffers of ports of the given type
+    fn port_type_buffer_size(&self, ptype: &str) -> usize {
+        let typestr = CString::new(ptype).unwrap();
+        let size = unsafe {
+            jack_sys::jack_port_type_get_buffer_size(self.get_raw(), typestr.as_ptr())
+        };
 
-        p.map(|p| unsafe { p.force_as_output::<DefaultAudioSample>() })
+        size as usize
     }
 
     /// Removes the port from the client and invalidates the port and all
     /// Handles relating to the port.
     ///
     /// The server disconnects everything that was previously connected to the port.
-    pub fn unregister_port<T: Port>(&mut self, port: T) -> Result<(), status::Status> {
-        let ret = unsafe { jack_sys::jack_port_unregister(self.c_client, port.get_raw()) };
+    fn unregister_port<T: Port>(&mut self, port: T) -> Result<(), Error> {
+        // the handle is invalid once the port is unregistered, so get the name first
+        let name = port.get_name();
+        let ret = unsafe { jack_sys::jack_port_unregister(self.get_raw(), port.get_raw()) };
 
         if ret == 0 {
             Ok(())
         } else {
-            // TODO try to handle this error code
-            Err(status::FAILURE)
+            Err(Error::PortUnregistrationFailed {
+                client: self.get_name(),
+                port:   name,
+                code:   ret,
+            })
         }
     }
 
-    pub fn get_port_by_name(&self, name: &str) -> Option<UnknownPortHandle> {
+    fn get_port_by_name(&self, name: &str) -> Option<UnknownPortHandle> {
         let cstr = CString::new(name).unwrap();
-        let ptr = unsafe { jack_sys::jack_port_by_name(self.c_client, cstr.as_ptr()) };
+        let ptr = unsafe { jack_sys::jack_port_by_name(self.get_raw(), cstr.as_ptr()) };
 
         if ptr.is_null() {
             None
         } else {
-            Some(UnknownPortHandle::new(ptr))
+            Some(unsafe { UnknownPortHandle::new(ptr) })
+        }
+    }
+
+    /// Returns the full names of all the ports known to the server which match the given
+    /// filters. `name_regex` and `type_regex` are extended regular expressions matched against
+    /// the full port name and the port type; `None` matches everything. Only ports with all of
+    /// the given `flags` are returned.
+    ///
+    /// Use `get_port_by_name` to get a handle to any of the ports
+    fn ports(
+        &self,
+        name_regex: Option<&str>,
+        type_regex: Option<&str>,
+        flags: port_flags::PortFlags)
+        -> PortNames
+    {
+        let name_regex = name_regex.map(|s| CString::new(s).unwrap());
+        let type_regex = type_regex.map(|s| CString::new(s).unwrap());
+
+        unsafe {
+            let names = jack_sys::jack_get_ports(
+                self.get_raw(),
+                name_regex.as_ref().map_or(ptr::null(), |s| s.as_ptr()),
+                type_regex.as_ref().map_or(ptr::null(), |s| s.as_ptr()),
+                flags.bits() as libc::c_ulong);
+
+            PortNames::new(names)
         }
     }
 
-    pub fn get_port_by_id(&self, id: PortId) -> Option<UnknownPortHandle> {
-        let ptr = unsafe { jack_sys::jack_port_by_id(self.c_client, id) };
+    /// Returns the full names of the physical audio playback ports, such as `system:playback_1`.
+    /// These are input ports, which the client's output ports can be connected to. Physical midi
+    /// ports are not included
+    fn physical_audio_playback_ports(&self) -> Vec<String> {
+        ports_of_type(
+            self,
+            port_type::DEFAULT_AUDIO_TYPE,
+            port_flags::PORT_IS_PHYSICAL | port_flags::PORT_IS_INPUT)
+    }
+
+    /// Returns the full names of the physical audio capture ports, such as `system:capture_1`.
+    /// These are output ports, which can be connected to the client's input ports. Physical midi
+    /// ports are not included
+    fn physical_audio_capture_ports(&self) -> Vec<String> {
+        ports_of_type(
+            self,
+            port_type::DEFAULT_AUDIO_TYPE,
+            port_flags::PORT_IS_PHYSICAL | port_flags::PORT_IS_OUTPUT)
+    }
+
+    /// Connects each of the given ports to a physical port of the same type, in order. Output
+    /// ports are connected to the physical playback ports: the first port to the first playback
+    /// port, the second port to the second, and so on. The physical capture ports are connected
+    /// to input ports in the same way.
+    ///
+    /// If there are more ports than physical ports, the extra ports are left alone. Returns the
+    /// number of connections made. The client must be active for the connections to be made.
+    ///
+    /// Output port handles can't be copied, so use `Port::as_unknown` to keep a handle for this
+    /// when the port itself is moved into a process handler
+    fn auto_connect<P: AutoConnectPort>(&mut self, ports: &[P]) -> Result<usize, Error> {
+        // the names, types and directions of the physical ports. The types are compared
+        // directly, jack would treat a type filter as a regex
+        let physical: Vec<(String, String, Direction)> =
+            self.ports(None, None, port_flags::PORT_IS_PHYSICAL)
+                .filter_map(|name| self.get_port_by_name(&name).map(|p| {
+                    // a physical input port plays back what our output ports send it
+                    let direction = if p.get_port_flags().contains(port_flags::PORT_IS_INPUT) {
+                        Direction::Playback
+                    } else {
+                        Direction::Capture
+                    };
+
+                    (name, p.port_type(), direction)
+                }))
+                .collect();
+        let mut used = vec![false; physical.len()];
+        let mut count = 0;
+
+        for port in ports {
+            let direction = port.direction();
+            let ptype = port.port_type();
+            let index = physical.iter().zip(used.iter())
+                .position(|(p, &u)| !u && p.1 == ptype && p.2 == direction);
+
+            let index = match index {
+                Some(index) => index,
+                None        => continue,
+            };
+
+            let ours = port.get_name();
+            let theirs = &physical[index].0;
+            try!(match direction {
+                Direction::Playback => self.connect_ports(&ours, theirs),
+                Direction::Capture  => self.connect_ports(theirs, &ours),
+            });
+
+            used[index] = true;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    fn get_port_by_id(&self, id: PortId) -> Option<UnknownPortHandle> {
+        let ptr = unsafe { jack_sys::jack_port_by_id(self.get_raw(), id) };
 
         if ptr.is_null() {
             None
         } else {
-            Some(UnknownPortHandle::new(ptr))
+            Some(unsafe { UnknownPortHandle::new(ptr) })
         }
     }
 
     /// Attempts to connect the ports with the given names
     /// Note that this method calls directly into the jack api. It does not
     /// perform lookups for the names before making the call
-    pub fn connect_ports(&mut self, port1: &str, port2: &str) -> Result<(), status::Status> {
+    fn connect_ports(&mut self, port1: &str, port2: &str) -> Result<(), Error> {
         let res = unsafe {
             jack_sys::jack_connect(
-                self.c_client,
+                self.get_raw(),
                 CString::new(port1).unwrap().as_ptr(),
                 CString::new(port2).unwrap().as_ptr())
         };
@@ -233,9 +485,12 @@ impl<'a> Client<'a> {
         if res == 0 {
             Ok(())
         } else {
-            // TODO figure out what these error codes mean
-            println!("error code: {}", res);
-            Err(status::FAILURE)
+            Err(Error::PortConnectionFailed {
+                client:      self.get_name(),
+                source:      port1.to_string(),
+                destination: port2.to_string(),
+                code:        res,
+            })
         }
     }
 
@@ -243,10 +498,10 @@ impl<'a> Client<'a> {
     /// Attempts to disconnect the ports with the given names
     /// Note that this method calls directly into the jack api. It does not
     /// perform lookups for the names before making the call
-    pub fn disconnect_ports(&mut self, port1: &str, port2: &str) -> Result<(), status::Status> {
+    fn disconnect_ports(&mut self, port1: &str, port2: &str) -> Result<(), Error> {
         let res = unsafe {
             jack_sys::jack_disconnect(
-                self.c_client,
+                self.get_raw(),
                 CString::new(port1).unwrap().as_ptr(),
                 CString::new(port2).unwrap().as_ptr())
         };
@@ -254,68 +509,487 @@ impl<'a> Client<'a> {
         if res == 0 {
             Ok(())
         } else {
-            Err(status::Status::from_bits(res as u32).unwrap())
+            Err(Error::PortDisconnectionFailed {
+                client:      self.get_name(),
+                source:      port1.to_string(),
+                destination: port2.to_string(),
+                code:        res,
+            })
+        }
+    }
+}
+
+/// A jack client connected to a jack server, which has not yet been activated.
+///
+/// Handlers may only be set on an inactive client. Once the handlers are in place, `activate`
+/// consumes the `Client` and returns an `ActiveClient`.
+///
+/// TODO example
+pub struct Client<'a> {
+    inner: ClientHandle<'a>,
+}
+
+/// A jack client which has been activated, and is now receiving callbacks from the server.
+///
+/// The handlers of an `ActiveClient` cannot be changed, because jack may call into them at any
+/// time. To change handlers, `deactivate` the client first.
+pub struct ActiveClient<'a> {
+    inner: ClientHandle<'a>,
+}
+
+/// Owns the underlying jack client and the handlers jack holds pointers to.
+///
+/// Both client states wrap one of these, so that the client is always deactivated and closed
+/// before the handlers are freed, no matter how the client goes away.
+struct ClientHandle<'a> {
+    c_client: *mut jack_sys::jack_client_t,
+    active:   bool,
+
+    // store the handlers in a box so that we can store a trait object + take ownership
+    // I do not like boxing everything up because it causes unnecessary heap allocation :(
+    process_handler:     Option<Box<ProcessContext<ProcessHandler + 'a>>>,
+    metadata_handler:    Option<Box<MetadataContext<MetadataHandler + 'a>>>,
+    thread_init_handler: Option<Box<ThreadInitHandler + 'a>>,
+    timebase_handler:    Option<Box<TimebaseHandler + 'a>>,
+    sync_handler:        Option<Box<SyncHandler + 'a>>,
+
+    // the callbacks which currently point at the metadata handler
+    metadata_callbacks: metadata_callbacks::MetadataCallbacks,
+
+    // shared with the xrun callback and any `XrunMonitor`s handed out
+    xruns: Arc<XrunState>,
+
+    // set by the freewheel callback, read by the process callback. jack holds a pointer to the
+    // flag whenever no metadata handler wants freewheel notifications, see `track_freewheel`
+    freewheeling: Arc<AtomicBool>,
+}
+
+/// The process handler, boxed up with the state its callback needs which isn't part of the
+/// handler itself.
+struct ProcessContext<T: ?Sized> {
+    c_client:     *mut jack_sys::jack_client_t,
+    freewheeling: Arc<AtomicBool>,
+    handler:      T,
+}
+
+/// The metadata handler, boxed up with the state its callbacks need which isn't part of the
+/// handler itself.
+struct MetadataContext<T: ?Sized> {
+    c_client:     *mut jack_sys::jack_client_t,
+    xruns:        Arc<XrunState>,
+    freewheeling: Arc<AtomicBool>,
+    handler:      T,
+}
+
+/// A snapshot of the state of the jack server, returned by `JackClient::server_info`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServerInfo {
+    /// the sample rate, in frames per second
+    pub sample_rate: NumFrames,
+
+    /// the current DSP load, as a percentage of the time available in each cycle
+    pub cpu_load: f32,
+
+    /// true if the server is running with real time scheduling
+    pub realtime: bool,
+
+    /// the size of the buffers passed to the process callback, in frames
+    pub buffer_size: NumFrames,
+}
+
+/// Statistics about the xruns seen by a client
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XrunStats {
+    /// The number of xruns reported to the client
+    pub count: usize,
+
+    /// The delay, in microseconds, which caused the most recent xrun
+    pub last_delay_usecs: f32,
+
+    /// The maximum delay, in microseconds, reported by jack when the most recent xrun occurred
+    pub max_delay_usecs: f32,
+}
+
+/// A handle to a client's xrun statistics. The monitor can be sent to another thread (a
+/// monitoring thread, for instance) and polled from there, even though the client itself cannot.
+///
+/// xruns are only recorded while the client's metadata handler is interested in
+/// `metadata_callbacks::XRUN`.
+#[derive(Clone)]
+pub struct XrunMonitor {
+    state: Arc<XrunState>,
+}
+
+impl XrunMonitor {
+    /// Returns a snapshot of the xrun statistics
+    pub fn stats(&self) -> XrunStats {
+        self.state.stats()
+    }
+}
+
+// the delays are f32s, stored as their bits so that they can live in an atomic
+struct XrunState {
+    count:            AtomicUsize,
+    last_delay_usecs: AtomicUsize,
+    max_delay_usecs:  AtomicUsize,
+}
+
+impl XrunState {
+    fn new() -> Self {
+        XrunState {
+            count:            AtomicUsize::new(0),
+            last_delay_usecs: AtomicUsize::new(0),
+            max_delay_usecs:  AtomicUsize::new(0),
+        }
+    }
+
+    /// Called from the xrun callback
+    unsafe fn record(&self, c_client: *mut jack_sys::jack_client_t) {
+        let last = jack_sys::jack_get_xrun_delayed_usecs(c_client);
+        let max  = jack_sys::jack_get_max_delayed_usecs(c_client);
+
+        self.last_delay_usecs.store(last.to_bits() as usize, Ordering::Relaxed);
+        self.max_delay_usecs.store(max.to_bits() as usize, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Release);
+    }
+
+    fn stats(&self) -> XrunStats {
+        let count = self.count.load(Ordering::Acquire);
+        let last  = self.last_delay_usecs.load(Ordering::Relaxed);
+        let max   = self.max_delay_usecs.load(Ordering::Relaxed);
+
+        XrunStats {
+            count:            count,
+            last_delay_usecs: f32::from_bits(last as u32),
+            max_delay_usecs:  f32::from_bits(max as u32),
+        }
+    }
+}
+
+impl<'a> ClientHandle<'a> {
+    /// Deactivates the client (if needed), then closes it.
+    /// Returns jack's return code from `jack_client_close`. Calling this more than once is a no-op.
+    fn close(&mut self) -> libc::c_int {
+        if self.c_client.is_null() {
+            return 0;
+        }
+
+        let ret = unsafe {
+            if self.active {
+                // jack would do this during close anyway, but this makes the ordering explicit
+                jack_sys::jack_deactivate(self.c_client);
+            }
+
+            jack_sys::jack_client_close(self.c_client)
+        };
+
+        // jack will not call into the handlers once the client is closed, even if close failed
+        self.c_client = ptr::null_mut();
+        self.active = false;
+        ret
+    }
+}
+
+impl<'a> Drop for ClientHandle<'a> {
+    fn drop(&mut self) {
+        // the handler boxes are dropped after this function returns, so jack is already done with
+        // them by the time they are freed.
+        // There isn't anything useful to do with an error here
+        self.close();
+    }
+}
+
+impl<'a> Client<'a> {
+    fn open_helper(cl: *mut jack_sys::jack_client_t, status: u32, name: &str)
+        -> Result<(Self, String), status::Status>
+    {
+        let status = status::Status::from_bits(status).unwrap();
+        if cl.is_null() {
+            Err(status)
+        } else {
+            // jack-sys looks jack_get_cycle_times up in the jack library the first time it is
+            // used. Do that now, instead of in the first process cycle which calls
+            // `CallbackContext::cycle_times`
+            let _ = jack_sys::jack_get_cycle_times.is_some();
+
+            let cl = Client {
+                inner: ClientHandle {
+                    c_client:            cl,
+                    active:              false,
+                    process_handler:     None,
+                    metadata_handler:    None,
+                    thread_init_handler: None,
+                    timebase_handler:    None,
+                    sync_handler:        None,
+                    metadata_callbacks:  metadata_callbacks::MetadataCallbacks::empty(),
+                    xruns:               Arc::new(XrunState::new()),
+                    freewheeling:        Arc::new(AtomicBool::new(false)),
+                }
+            };
+
+            // jack only fails to set callbacks on active clients, and this one is brand new
+            unsafe { track_freewheel(cl.inner.c_client, &cl.inner.freewheeling) };
+
+            let name = if status.contains(status::NAME_NOT_UNIQUE) {
+                cl.get_name()
+            } else {
+                name.to_string()
+            };
+
+            Ok( (cl, name) )
+        }
+    }
+
+    /// Creates a new client and connects it to the default jack server. The
+    /// client will use the name given. If the name is not unique, the behavior
+    /// depends on the options provided via `opts`.
+    ///
+    /// If the option to force a unique name is given (USE_EXACT_NAME) and the exact name can not
+    /// be given, Err will be returned. Otherwise Returns the client and the name assigned to the
+    /// client.
+    ///
+    /// TODO client_name_size details in docs and in code
+    pub fn open(name: &str, opts: options::Options) -> Result<(Self, String), status::Status> {
+        // TODO does jack check if the options are valid?
+        // TODO does jack check if the name is too large?
+
+        let cstr       = CString::new(name).unwrap();
+        let mut status = 0 as jack_sys::jack_status_t;
+        let statusptr  = &mut status as *mut jack_sys::jack_status_t;
+
+        let cl = unsafe { jack_sys::jack_client_open(cstr.as_ptr(), opts.bits(), statusptr) };
+        Client::open_helper(cl, status, name)
+    }
+
+    /// Attempts to open a client connecting to a server with a specified name
+    pub fn open_connection_to(
+        clientname: &str,
+        servername: &str,
+        opts: options::Options)
+        -> Result<(Self, String), status::Status>
+    {
+        let cstr       = CString::new(clientname).unwrap();
+        let sstr       = CString::new(servername).unwrap();
+        let mut status = 0 as jack_sys::jack_status_t;
+        let statusptr  = &mut status as *mut jack_sys::jack_status_t;
+
+        let additionalopts = options::Options::from_bits(jack_sys::JackServerName).unwrap();
+        let cl = unsafe {
+            jack_sys::jack_client_open(
+                cstr.as_ptr(),
+                (opts | additionalopts).bits(),
+                statusptr,
+                sstr.as_ptr())
+        };
+
+        Client::open_helper(cl, status, clientname)
+    }
+
+    /// Set the client's process callback handler.
+    /// The client takes ownership of the handler, so be sure to set up any
+    /// messaging queues before passing the handler off to the client
+    /// See the docs for the `ProcessHandler` struct for more details
+    pub fn set_process_handler<T: ProcessHandler + 'a>(&mut self, handler: T)
+        -> Result<(), Error>
+    {
+        // a function which will do some setup then call the client's handler
+        // this function must be generic over <T>.
+        // Trait pointers are "fat pointers" (not raw pointers), so we can't
+        // pass trait pointers around via a C void*
+        unsafe extern "C" fn process_callback<T: ProcessHandler>(
+            nframes: jack_sys::jack_nframes_t,
+            args: *mut libc::c_void)
+            -> libc::c_int
+        {
+            let this = args as *mut ProcessContext<T>;
+            let freewheeling = (*this).freewheeling.load(Ordering::Relaxed);
+            let ctx = CallbackContext::new((*this).c_client, freewheeling);
+            (*this).handler.process(&ctx, nframes)
+        }
+
+        // create a box for this handler
+        // this will allocate memory and move the object to the allocated memory
+        // on the heap
+        let b = Box::new(ProcessContext {
+            c_client:     self.inner.c_client,
+            freewheeling: self.inner.freewheeling.clone(),
+            handler:      handler,
+        });
+
+        // get the pointer, this consumes the box, but does not move the
+        // resulting memory anywhere
+        let ptr = Box::into_raw(b);
+
+        let ret = unsafe {
+            let ptr = ptr as *mut libc::c_void;
+            jack_sys::jack_set_process_callback(
+                self.inner.c_client, Some(process_callback::<T>), ptr)
+        };
+
+        if ret != 0 {
+            // jack did not take the handler, so we still own it
+            drop(unsafe { Box::from_raw(ptr) });
+            Err(Error::CallbackRegistrationFailed {
+                client:   self.get_name(),
+                callback: "process",
+                code:     ret,
+            })
+        } else {
+            // create a box from the raw pointer. this does not allocate more memory
+            let b = unsafe { Box::from_raw(ptr) };
+            self.inner.process_handler = Some(b);
+            Ok(())
+        }
+    }
+
+    /// Set the client's thread init handler, which jack calls once in each thread it creates for
+    /// this client. See the docs for the `ThreadInitHandler` trait for more details
+    pub fn set_thread_init_handler<T: ThreadInitHandler + 'a>(&mut self, handler: T)
+        -> Result<(), Error>
+    {
+        unsafe extern "C" fn thread_init_callback<T: ThreadInitHandler>(args: *mut libc::c_void) {
+            let this = args as *const T;
+            (*this).thread_init()
+        }
+
+        let ptr = Box::into_raw(Box::new(handler));
+
+        let ret = unsafe {
+            jack_sys::jack_set_thread_init_callback(
+                self.inner.c_client, Some(thread_init_callback::<T>), ptr as *mut libc::c_void)
+        };
+
+        // create a box from the raw pointer. this does not allocate more memory
+        let b = unsafe { Box::from_raw(ptr) };
+
+        if ret != 0 {
+            // jack did not take the handler, so it is dropped here
+            Err(Error::CallbackRegistrationFailed {
+                client:   self.get_name(),
+                callback: "thread init",
+                code:     ret,
+            })
+        } else {
+            self.inner.thread_init_handler = Some(b);
+            Ok(())
+        }
+    }
+
+    /// Set the client's timebase handler, making this client the timebase master. See the docs
+    /// for the `TimebaseHandler` trait for more details.
+    ///
+    /// If `conditional` is true, this fails if there is already a timebase master. Otherwise
+    /// this client takes over from any existing master.
+    /// Use `release_timebase` to stop being the master
+    pub fn set_timebase_handler<T: TimebaseHandler + 'a>(&mut self, handler: T, conditional: bool)
+        -> Result<(), Error>
+    {
+        unsafe extern "C" fn timebase_callback<T: TimebaseHandler>(
+            state: jack_sys::jack_transport_state_t,
+            nframes: jack_sys::jack_nframes_t,
+            pos: *mut jack_sys::jack_position_t,
+            new_pos: libc::c_int,
+            args: *mut libc::c_void)
+        {
+            let this = args as *mut T;
+            let mut position = Position::from_raw(&*pos);
+            let state = TransportState::from_raw(state);
+
+            (*this).update_position(state, nframes, &mut position, new_pos != 0);
+
+            // only the bar, beat and tick information belongs to the timebase master
+            position.write_bbt(&mut *pos);
+        }
+
+        let ptr = Box::into_raw(Box::new(handler));
+
+        let ret = unsafe {
+            jack_sys::jack_set_timebase_callback(
+                self.inner.c_client,
+                conditional as libc::c_int,
+                Some(timebase_callback::<T>),
+                ptr as *mut libc::c_void)
+        };
+
+        // create a box from the raw pointer. this does not allocate more memory
+        let b = unsafe { Box::from_raw(ptr) };
+
+        if ret != 0 {
+            // jack did not take the handler, so it is dropped here
+            Err(Error::CallbackRegistrationFailed {
+                client:   self.get_name(),
+                callback: "timebase",
+                code:     ret,
+            })
+        } else {
+            self.inner.timebase_handler = Some(b);
+            Ok(())
         }
     }
 
-    /// Set the client's process callback handler.
-    /// The client takes ownership of the handler, so be sure to set up any
-    /// messaging queues before passing the handler off to the client
-    /// See the docs for the `ProcessHandler` struct for more details
-    pub fn set_process_handler<T: ProcessHandler + 'a>(&mut self, handler: T)
-        -> Result<(), status::Status>
-    {
-        // a function which will do some setup then call the client's handler
-        // this function must be generic over <T>.
-        // Trait pointers are "fat pointers" (not raw pointers), so we can't
-        // pass trait pointers around via a C void*
-        unsafe extern "C" fn process_callback<T: ProcessHandler>(
-            nframes: jack_sys::jack_nframes_t,
-            args: *mut libc::c_void)
-            -> libc::c_int
+    /// Set the client's sync handler, making this a slow-sync client. See the docs for the
+    /// `SyncHandler` trait for more details
+    pub fn set_sync_handler<T: SyncHandler + 'a>(&mut self, handler: T) -> Result<(), Error> {
+        unsafe extern "C" fn sync_callback<T: SyncHandler>(
+            state: jack_sys::jack_transport_state_t,
+            pos: *mut jack_sys::jack_position_t,
+            args: *mut libc::c_void) -> libc::c_int
         {
             let this = args as *mut T;
-            let ctx = CallbackContext::new();
-            (*this).process(&ctx, nframes)
-        }
+            let position = Position::from_raw(&*pos);
+            let state = TransportState::from_raw(state);
 
-        // create a box for this handler
-        // this will allocate memory and move the object to the allocated memory
-        // on the heap
-        let b = Box::new(handler);
+            (*this).ready_to_roll(state, &position) as libc::c_int
+        }
 
-        // get the pointer, this consumes the box, but does not move the
-        // resulting memory anywhere
-        let ptr = Box::into_raw(b);
+        let ptr = Box::into_raw(Box::new(handler));
 
         let ret = unsafe {
-            let ptr = ptr as *mut libc::c_void;
-            jack_sys::jack_set_process_callback(
-                self.c_client, Some(process_callback::<T>), ptr)
+            jack_sys::jack_set_sync_callback(
+                self.inner.c_client, Some(sync_callback::<T>), ptr as *mut libc::c_void)
         };
 
+        // create a box from the raw pointer. this does not allocate more memory
+        let b = unsafe { Box::from_raw(ptr) };
+
         if ret != 0 {
-            // again, no error code provided
-            Err(status::FAILURE)
+            // jack did not take the handler, so it is dropped here
+            Err(Error::CallbackRegistrationFailed {
+                client:   self.get_name(),
+                callback: "sync",
+                code:     ret,
+            })
         } else {
-            // create a box from the raw pointer. this does not allocate more memory
-            let b = unsafe { Box::from_raw(ptr) };
-            self.process_handler = Some(b);
+            self.inner.sync_handler = Some(b);
             Ok(())
         }
     }
 
-    /// Set the client's sample rate change handler.
+    /// Set the client's metadata handler. The handler receives each of the callbacks selected by
+    /// its `callbacks_of_interest`.
+    ///
+    /// The callbacks are registered all-or-nothing. If any one of them can't be registered, none
+    /// of them are, the client is left without a metadata handler, and the error names the
+    /// callback which failed.
     pub fn set_metadata_handler<T: MetadataHandler + 'a>(&mut self, handler: T)
-        -> Result<(), status::Status>
+        -> Result<(), Error>
     {
         unsafe extern "C" fn srate_callback<T: MetadataHandler>(
             srate: NumFrames,
             args: *mut libc::c_void) -> i32
         {
-            let this = args as *mut T;
+            let this = args as *mut MetadataContext<T>;
+
+            (*this).handler.sample_rate_changed(srate)
+        }
+
+        unsafe extern "C" fn bufsize_callback<T: MetadataHandler>(
+            nframes: NumFrames,
+            args: *mut libc::c_void) -> i32
+        {
+            let this = args as *mut MetadataContext<T>;
 
-            (*this).sample_rate_changed(srate)
+            (*this).handler.buffer_size_changed(nframes)
         }
 
         unsafe extern "C" fn connect_callback<T: MetadataHandler>(
@@ -324,95 +998,383 @@ impl<'a> Client<'a> {
             connect: libc::c_int,
             args: *mut libc::c_void)
         {
-            let this = args as *mut T;
+            let this = args as *mut MetadataContext<T>;
             let status = if connect == 0 {
                 PortConnectStatus::PortsDisconnected
             } else {
                 PortConnectStatus::PortsConnected
             };
 
-            (*this).on_port_connect(a, b, status)
+            (*this).handler.on_port_connect(a, b, status)
         }
 
-        let b = Box::new(handler);
-        let cbs = b.callbacks_of_interest();
+        unsafe extern "C" fn client_registration_callback<T: MetadataHandler>(
+            name: *const libc::c_char,
+            registered: libc::c_int,
+            args: *mut libc::c_void)
+        {
+            let this = args as *mut MetadataContext<T>;
+            let name = CStr::from_ptr(name).to_string_lossy();
 
-        let ptr = Box::into_raw(b);
+            (*this).handler.on_client_registration(&name, registered != 0)
+        }
 
-        let ret = unsafe {
-            let ptr = ptr as *mut libc::c_void;
+        unsafe extern "C" fn port_registration_callback<T: MetadataHandler>(
+            port: jack_sys::jack_port_id_t,
+            registered: libc::c_int,
+            args: *mut libc::c_void)
+        {
+            let this = args as *mut MetadataContext<T>;
 
-            let mut ret = 0;
-            for h in cbs {
-                ret = match h {
-                    MetadataHandlers::SampleRate =>
-                        jack_sys::jack_set_sample_rate_callback(
-                            self.c_client, Some(srate_callback::<T>), ptr),
-
-                    MetadataHandlers::PortConnect =>
-                        jack_sys::jack_set_port_connect_callback(
-                            self.c_client, Some(connect_callback::<T>), ptr),
-
-                    // MetadataHandlers::Shutdown
-                    // MetadataHandlers::Freewheel,
-                    // MetadataHandlers::BufferSize,
-                    // MetadataHandlers::ClientRegistration,
-                    // MetadataHandlers::PortRegistration,
-                    // MetadataHandlers::PortRename,
-                    // MetadataHandlers::GraphOrder,
-                    // MetadataHandlers::Xrun,
-                    _           => unimplemented!(),
-                };
+            (*this).handler.on_port_registration(port, registered != 0)
+        }
+
+        unsafe extern "C" fn port_rename_callback<T: MetadataHandler>(
+            port: jack_sys::jack_port_id_t,
+            old_name: *const libc::c_char,
+            new_name: *const libc::c_char,
+            args: *mut libc::c_void) -> libc::c_int
+        {
+            let this = args as *mut MetadataContext<T>;
+            let old_name = CStr::from_ptr(old_name).to_string_lossy();
+            let new_name = CStr::from_ptr(new_name).to_string_lossy();
+
+            (*this).handler.on_port_rename(port, &old_name, &new_name);
+            0
+        }
+
+        unsafe extern "C" fn graph_order_callback<T: MetadataHandler>(
+            args: *mut libc::c_void) -> libc::c_int
+        {
+            let this = args as *mut MetadataContext<T>;
+
+            (*this).handler.graph_order_changed()
+        }
+
+        unsafe extern "C" fn shutdown_callback<T: MetadataHandler>(
+            code: jack_sys::jack_status_t,
+            reason: *const libc::c_char,
+            args: *mut libc::c_void)
+        {
+            let this = args as *mut MetadataContext<T>;
+            let status = status::Status::from_bits_truncate(code);
+            let reason = CStr::from_ptr(reason).to_string_lossy();
+
+            (*this).handler.on_shutdown(status, &reason)
+        }
+
+        unsafe extern "C" fn xrun_callback<T: MetadataHandler>(args: *mut libc::c_void) -> i32 {
+            let this = args as *mut MetadataContext<T>;
+            (*this).xruns.record((*this).c_client);
+
+            (*this).handler.on_xrun()
+        }
+
+        unsafe extern "C" fn freewheel_callback<T: MetadataHandler>(
+            starting: libc::c_int,
+            args: *mut libc::c_void)
+        {
+            let this = args as *mut MetadataContext<T>;
+            (*this).freewheeling.store(starting != 0, Ordering::Relaxed);
 
+            (*this).handler.freewheel_changed(starting != 0)
+        }
+
+        // points the callback `cb` (a single flag) at the handler behind `ptr`, or clears the
+        // callback if `ptr` is null
+        unsafe fn set_callback<T: MetadataHandler>(
+            c_client: *mut jack_sys::jack_client_t,
+            cb: metadata_callbacks::MetadataCallbacks,
+            ptr: *mut libc::c_void)
+            -> libc::c_int
+        {
+            let on = !ptr.is_null();
+
+            match cb {
+                metadata_callbacks::SAMPLE_RATE =>
+                    jack_sys::jack_set_sample_rate_callback(
+                        c_client, if on { Some(srate_callback::<T>) } else { None }, ptr),
+
+                metadata_callbacks::BUFFER_SIZE =>
+                    jack_sys::jack_set_buffer_size_callback(
+                        c_client, if on { Some(bufsize_callback::<T>) } else { None }, ptr),
+
+                metadata_callbacks::PORT_CONNECT =>
+                    jack_sys::jack_set_port_connect_callback(
+                        c_client, if on { Some(connect_callback::<T>) } else { None }, ptr),
+
+                metadata_callbacks::CLIENT_REGISTRATION =>
+                    jack_sys::jack_set_client_registration_callback(
+                        c_client,
+                        if on { Some(client_registration_callback::<T>) } else { None },
+                        ptr),
+
+                metadata_callbacks::PORT_REGISTRATION =>
+                    jack_sys::jack_set_port_registration_callback(
+                        c_client,
+                        if on { Some(port_registration_callback::<T>) } else { None },
+                        ptr),
+
+                metadata_callbacks::PORT_RENAME =>
+                    jack_sys::jack_set_port_rename_callback(
+                        c_client, if on { Some(port_rename_callback::<T>) } else { None }, ptr),
+
+                metadata_callbacks::GRAPH_ORDER =>
+                    jack_sys::jack_set_graph_order_callback(
+                        c_client, if on { Some(graph_order_callback::<T>) } else { None }, ptr),
+
+                metadata_callbacks::XRUN =>
+                    jack_sys::jack_set_xrun_callback(
+                        c_client, if on { Some(xrun_callback::<T>) } else { None }, ptr),
+
+                metadata_callbacks::FREEWHEEL =>
+                    jack_sys::jack_set_freewheel_callback(
+                        c_client, if on { Some(freewheel_callback::<T>) } else { None }, ptr),
+
+                metadata_callbacks::SHUTDOWN => {
+                    // this one can't fail
+                    jack_sys::jack_on_info_shutdown(
+                        c_client, if on { Some(shutdown_callback::<T>) } else { None }, ptr);
+                    0
+                },
+
+                // not a single callback, there is nothing to set
+                _ => 0,
+            }
+        }
+
+        // every callback, with the name used to report errors
+        let all = [
+            (metadata_callbacks::SAMPLE_RATE,         "sample rate"),
+            (metadata_callbacks::BUFFER_SIZE,         "buffer size"),
+            (metadata_callbacks::PORT_CONNECT,        "port connect"),
+            (metadata_callbacks::CLIENT_REGISTRATION, "client registration"),
+            (metadata_callbacks::PORT_REGISTRATION,   "port registration"),
+            (metadata_callbacks::PORT_RENAME,         "port rename"),
+            (metadata_callbacks::GRAPH_ORDER,         "graph order"),
+            (metadata_callbacks::XRUN,                "xrun"),
+            (metadata_callbacks::FREEWHEEL,           "freewheel"),
+            (metadata_callbacks::SHUTDOWN,            "shutdown"),
+        ];
+
+        let c_client = self.inner.c_client;
+        let b = Box::new(MetadataContext {
+            c_client:     c_client,
+            xruns:        self.inner.xruns.clone(),
+            freewheeling: self.inner.freewheeling.clone(),
+            handler:      handler,
+        });
+        let cbs = b.handler.callbacks_of_interest();
+
+        let ptr = Box::into_raw(b);
+
+        // register the callbacks one at a time, stopping at the first failure
+        let mut failure = None;
+        for &(cb, name) in all.iter() {
+            if cbs.contains(cb) {
+                let ret = unsafe { set_callback::<T>(c_client, cb, ptr as *mut libc::c_void) };
                 if ret != 0 {
+                    failure = Some((name, ret));
                     break;
                 }
             }
+        }
 
-            ret
+        // Anything the previous handler registered which wasn't just replaced still points at the
+        // previous handler, which is about to be freed. After a failure, everything goes.
+        let stale = match failure {
+            None    => self.inner.metadata_callbacks - cbs,
+            Some(_) => self.inner.metadata_callbacks | cbs,
         };
 
-        if ret != 0 {
-            // again, no error code provided
-            Err(status::FAILURE)
-        } else {
-            // create a box from the raw pointer. this does not allocate more memory
-            let b = unsafe { Box::from_raw(ptr) };
-            self.metadata_handler = Some(b);
-            Ok(())
+        let mut cleared = true;
+        for &(cb, _) in all.iter() {
+            if stale.contains(cb) {
+                let ret = if cb == metadata_callbacks::FREEWHEEL {
+                    // keep `CallbackContext::is_freewheeling` up to date without the handler
+                    unsafe { track_freewheel(c_client, &self.inner.freewheeling) }
+                } else {
+                    unsafe { set_callback::<T>(c_client, cb, ptr::null_mut()) }
+                };
+
+                cleared = cleared && ret == 0;
+            }
+        }
+
+        // create a box from the raw pointer. this does not allocate more memory
+        let b = unsafe { Box::from_raw(ptr) };
+
+        // If a callback could not be cleared, jack may still call into the previous handler, or
+        // into the new one after a failure. Leak them rather than leave jack a dangling pointer
+        if !cleared {
+            if let Some(old) = self.inner.metadata_handler.take() {
+                mem::forget(old);
+            }
+        }
+
+        match failure {
+            None => {
+                self.inner.metadata_handler   = Some(b);
+                self.inner.metadata_callbacks = cbs;
+                Ok(())
+            },
+
+            Some((name, code)) => {
+                if !cleared {
+                    mem::forget(b);
+                }
+
+                self.inner.metadata_handler   = None;
+                self.inner.metadata_callbacks = metadata_callbacks::MetadataCallbacks::empty();
+
+                Err(Error::CallbackRegistrationFailed {
+                    client:   self.get_name(),
+                    callback: name,
+                    code:     code,
+                })
+            }
         }
     }
 
     /// tells the JACK server that the client is read to start processing audio
     /// This will initiate
     /// callbacks into the `CallbackHandler` provided.
-    pub fn activate(&self) -> Result<(), status::Status> {
-        // TODO disable various other function calls after activate is called
-        // do this via (self) -> ActivatedClient or something
-        let ret = unsafe { jack_sys::jack_activate(self.c_client) };
+    ///
+    /// The `Client` is consumed and an `ActiveClient` is returned, which does not allow the
+    /// handlers to be replaced while jack may be calling into them.
+    /// If activation fails, the client is handed back with the error, so that activation can be
+    /// retried without losing the ports and handlers.
+    pub fn activate(self) -> Result<ActiveClient<'a>, (Client<'a>, Error)> {
+        let ret = unsafe { jack_sys::jack_activate(self.inner.c_client) };
 
         if ret != 0 {
-            // TODO handle error
-            Err(status::FAILURE)
+            let err = Error::ActivateFailed { client: self.get_name(), code: ret };
+            Err((self, err))
         } else {
-            Ok(())
+            let mut inner = self.inner;
+            inner.active = true;
+            Ok(ActiveClient { inner: inner })
         }
     }
 
     /// Disconnects the client from the JACK server.
     /// This will also disconnect and destroy any of the ports which the client registered
-    pub fn close(&mut self) -> Result<(), &str> {
-        let ret = unsafe { jack_sys::jack_client_close(self.c_client) };
+    ///
+    /// Dropping the client will also close it, but any error will be lost.
+    pub fn close(self) -> Result<(), Error> {
+        // the name is not available after the client is closed
+        let name = self.get_name();
+        let mut inner = self.inner;
+
+        match inner.close() {
+            0    => Ok(()),
+            code => Err(Error::CloseFailed { client: name, code: code }),
+        }
+    }
 
-        if ret == 0 {
-            Ok(())
+    /// Returns a handle to this client's xrun statistics, which can be polled from any thread.
+    /// See `XrunMonitor`
+    pub fn xrun_monitor(&self) -> XrunMonitor {
+        XrunMonitor { state: self.inner.xruns.clone() }
+    }
+}
+
+impl<'a> fmt::Debug for Client<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Client").field("name", &self.get_name()).finish()
+    }
+}
+
+impl<'a> sealed::Sealed for Client<'a> { }
+impl<'a> JackClient for Client<'a> {
+    #[doc(hidden)]
+    unsafe fn get_raw(&self) -> *mut jack_sys::jack_client_t { self.inner.c_client }
+}
+
+impl<'a> ActiveClient<'a> {
+    /// tells the JACK server to remove this client from the process graph. This also disconnects
+    /// all of the client's ports.
+    ///
+    /// Once the server has stopped calling into the client's handlers, the inactive `Client` is
+    /// returned so that the handlers may be replaced.
+    /// If deactivation fails, the client is handed back with the error, still active.
+    pub fn deactivate(self) -> Result<Client<'a>, (ActiveClient<'a>, Error)> {
+        let ret = unsafe { jack_sys::jack_deactivate(self.inner.c_client) };
+
+        if ret != 0 {
+            let err = Error::DeactivateFailed { client: self.get_name(), code: ret };
+            Err((self, err))
         } else {
-            Err("some error should go here")
+            let mut inner = self.inner;
+            inner.active = false;
+            Ok(Client { inner: inner })
         }
     }
 
-    #[cfg(test)]
-    pub unsafe fn get_raw(&self) -> *const jack_sys::jack_client_t { self.c_client }
+    /// Deactivates the client and disconnects it from the JACK server.
+    /// This will also disconnect and destroy any of the ports which the client registered
+    ///
+    /// Dropping the client will also close it, but any error will be lost.
+    pub fn close(self) -> Result<(), Error> {
+        // the name is not available after the client is closed
+        let name = self.get_name();
+        let mut inner = self.inner;
+
+        match inner.close() {
+            0    => Ok(()),
+            code => Err(Error::CloseFailed { client: name, code: code }),
+        }
+    }
+
+    /// Returns a handle to this client's xrun statistics, which can be polled from any thread.
+    /// See `XrunMonitor`
+    pub fn xrun_monitor(&self) -> XrunMonitor {
+        XrunMonitor { state: self.inner.xruns.clone() }
+    }
+}
+
+impl<'a> fmt::Debug for ActiveClient<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ActiveClient").field("name", &self.get_name()).finish()
+    }
+}
+
+impl<'a> sealed::Sealed for ActiveClient<'a> { }
+impl<'a> JackClient for ActiveClient<'a> {
+    #[doc(hidden)]
+    unsafe fn get_raw(&self) -> *mut jack_sys::jack_client_t { self.inner.c_client }
+}
+
+/// Returns the full names of the ports with all of the given `flags` and exactly the type `ptype`.
+/// jack would treat a type filter as a regex, so the types are compared here instead
+fn ports_of_type<C: JackClient + ?Sized>(client: &C, ptype: &str, flags: port_flags::PortFlags)
+    -> Vec<String>
+{
+    client.ports(None, None, flags)
+        .filter(|name| match client.get_port_by_name(name) {
+            Some(port) => port.port_type() == ptype,
+            None       => false,
+        })
+        .collect()
+}
+
+/// Points jack's freewheel callback at `freewheeling` alone, for when no metadata handler wants
+/// to hear about freewheel mode
+unsafe fn track_freewheel(c_client: *mut jack_sys::jack_client_t, freewheeling: &AtomicBool)
+    -> libc::c_int
+{
+    unsafe extern "C" fn freewheel_callback(starting: libc::c_int, args: *mut libc::c_void) {
+        let freewheeling = args as *const AtomicBool;
+        (*freewheeling).store(starting != 0, Ordering::Relaxed);
+    }
+
+    let ptr = freewheeling as *const AtomicBool as *mut libc::c_void;
+    jack_sys::jack_set_freewheel_callback(c_client, Some(freewheel_callback), ptr)
+}
+
+mod sealed {
+    /// Keeps other crates from implementing `JackClient`
+    pub trait Sealed { }
 }
 
 // these tests are extremely fragile because they involve using a c library as the stub mechanism
@@ -448,6 +1410,45 @@ mod test {
         pub fn jgcn_get_num_calls() -> libc::size_t;
         pub fn jgcn_setup();
         pub fn jgcn_cleanup();
+
+        // jack_client_close
+        pub fn jcc_set_return(ret: libc::c_int);
+        pub fn jcc_get_passed_client() -> *mut jack_sys::jack_client_t;
+        pub fn jcc_get_num_calls() -> libc::size_t;
+        pub fn jcc_setup();
+        pub fn jcc_cleanup();
+
+        // jack_activate
+        pub fn ja_set_return(ret: libc::c_int);
+        pub fn ja_get_num_calls() -> libc::size_t;
+        pub fn ja_setup();
+        pub fn ja_cleanup();
+
+        // jack_set_freewheel_callback
+        pub fn jsfc_is_set() -> libc::c_int;
+        pub fn jsfc_call(starting: libc::c_int);
+        pub fn jsfc_setup();
+        pub fn jsfc_cleanup();
+
+        // jack_set_sample_rate_callback
+        pub fn jssrc_is_set() -> libc::c_int;
+        pub fn jssrc_get_arg() -> *mut libc::c_void;
+        pub fn jssrc_setup();
+        pub fn jssrc_cleanup();
+
+        // jack_set_buffer_size_callback
+        pub fn jsbsc_is_set() -> libc::c_int;
+        pub fn jsbsc_get_arg() -> *mut libc::c_void;
+        pub fn jsbsc_get_num_calls() -> libc::size_t;
+        pub fn jsbsc_setup();
+        pub fn jsbsc_cleanup();
+
+        // jack_set_xrun_callback
+        pub fn jsxc_set_return(ret: libc::c_int);
+        pub fn jsxc_is_set() -> libc::c_int;
+        pub fn jsxc_get_arg() -> *mut libc::c_void;
+        pub fn jsxc_setup();
+        pub fn jsxc_cleanup();
     }
 
     struct JackClientOpen { }
@@ -458,6 +1459,51 @@ mod test {
     impl JackGetClientName { pub fn setup() { unsafe { jgcn_setup(); } } }
     impl Drop for JackGetClientName { fn drop(&mut self) { unsafe { jgcn_cleanup(); } } }
 
+    struct JackClientClose { }
+    impl JackClientClose { pub fn setup() { unsafe { jcc_setup(); } } }
+    impl Drop for JackClientClose { fn drop(&mut self) { unsafe { jcc_cleanup(); } } }
+
+    struct JackActivate { }
+    impl JackActivate { pub fn setup() { unsafe { ja_setup(); } } }
+    impl Drop for JackActivate { fn drop(&mut self) { unsafe { ja_cleanup(); } } }
+
+    // sets up all of the callback setters the metadata handler tests use
+    struct JackSetCallbacks { }
+    impl JackSetCallbacks {
+        pub fn setup() { unsafe { jssrc_setup(); jsbsc_setup(); jsxc_setup(); jsfc_setup(); } }
+    }
+    impl Drop for JackSetCallbacks {
+        fn drop(&mut self) {
+            unsafe { jssrc_cleanup(); jsbsc_cleanup(); jsxc_cleanup(); jsfc_cleanup(); }
+        }
+    }
+
+    struct TestMetadataHandler {
+        callbacks: metadata_callbacks::MetadataCallbacks,
+    }
+
+    impl MetadataHandler for TestMetadataHandler {
+        fn callbacks_of_interest(&self) -> metadata_callbacks::MetadataCallbacks {
+            self.callbacks
+        }
+    }
+
+    // records when it is freed
+    struct DropMetadataHandler {
+        callbacks: metadata_callbacks::MetadataCallbacks,
+        dropped:   Arc<AtomicBool>,
+    }
+
+    impl MetadataHandler for DropMetadataHandler {
+        fn callbacks_of_interest(&self) -> metadata_callbacks::MetadataCallbacks {
+            self.callbacks
+        }
+    }
+
+    impl Drop for DropMetadataHandler {
+        fn drop(&mut self) { self.dropped.store(true, Ordering::Relaxed) }
+    }
+
     #[test]
     fn test_client_open_fail() {
         let _jco = JackClientOpen::setup();
@@ -611,4 +1657,257 @@ mod test {
 
         assert!(unsafe { jco_get_num_calls() } == 1);
     }
+
+    #[test]
+    fn test_client_drop_closes() {
+        let (_co, _cc) = (JackClientOpen::setup(), JackClientClose::setup());
+
+        let ptr = 0xdeadbeef as *mut jack_sys::jack_client_t;
+        unsafe { jco_set_return(ptr) };
+
+        let client = Client::open("test", options::NO_START_SERVER);
+        assert!(client.is_ok());
+        assert!(unsafe { jcc_get_num_calls() } == 0);
+
+        drop(client);
+
+        unsafe {
+            assert!(jcc_get_num_calls() == 1);
+            assert!(jcc_get_passed_client() == ptr);
+        }
+    }
+
+    #[test]
+    fn test_client_close_okay() {
+        let (_co, _gn, _cc) =
+            (JackClientOpen::setup(), JackGetClientName::setup(), JackClientClose::setup());
+
+        let ptr = 0xdeadbeef as *mut jack_sys::jack_client_t;
+        let name = CString::new("test").unwrap();
+        unsafe {
+            jco_set_return(ptr);
+            jgcn_set_return(name.as_ptr());
+        }
+
+        let client = Client::open("test", options::NO_START_SERVER).unwrap().0;
+        assert!(client.close().is_ok());
+
+        // the client was consumed, so it must not have been closed again when it was dropped
+        unsafe {
+            assert!(jcc_get_num_calls() == 1);
+            assert!(jcc_get_passed_client() == ptr);
+        }
+    }
+
+    #[test]
+    fn test_client_close_fail() {
+        let (_co, _gn, _cc) =
+            (JackClientOpen::setup(), JackGetClientName::setup(), JackClientClose::setup());
+
+        let ptr = 0xdeadbeef as *mut jack_sys::jack_client_t;
+        let name = CString::new("test").unwrap();
+        unsafe {
+            jco_set_return(ptr);
+            jgcn_set_return(name.as_ptr());
+            jcc_set_return(-1);
+        }
+
+        let client = Client::open("test", options::NO_START_SERVER).unwrap().0;
+        let err = client.close();
+        assert!(err == Err(Error::CloseFailed { client: "test".to_string(), code: -1 }));
+
+        assert!(unsafe { jcc_get_num_calls() } == 1);
+    }
+
+    #[test]
+    fn test_metadata_handler_replace_clears_stale() {
+        let (_co, _gn, _cc, _sc) = (
+            JackClientOpen::setup(),
+            JackGetClientName::setup(),
+            JackClientClose::setup(),
+            JackSetCallbacks::setup());
+
+        let ptr = 0xdeadbeef as *mut jack_sys::jack_client_t;
+        unsafe { jco_set_return(ptr) };
+
+        let mut client = Client::open("test", options::NO_START_SERVER).unwrap().0;
+
+        let first = TestMetadataHandler {
+            callbacks: metadata_callbacks::SAMPLE_RATE | metadata_callbacks::BUFFER_SIZE
+        };
+        assert!(client.set_metadata_handler(first).is_ok());
+
+        let first_arg = unsafe { jsbsc_get_arg() };
+        unsafe {
+            assert!(jssrc_is_set() != 0);
+            assert!(jsbsc_is_set() != 0);
+            assert!(jsxc_is_set() == 0);
+        }
+
+        let second = TestMetadataHandler {
+            callbacks: metadata_callbacks::BUFFER_SIZE | metadata_callbacks::XRUN
+        };
+        assert!(client.set_metadata_handler(second).is_ok());
+
+        unsafe {
+            // only the sample rate callback (old - new) is cleared
+            assert!(jssrc_is_set() == 0);
+            assert!(jssrc_get_arg().is_null());
+
+            // the others point at the new handler
+            assert!(jsbsc_is_set() != 0);
+            assert!(jsxc_is_set() != 0);
+            assert!(jsbsc_get_arg() != first_arg);
+            assert!(jsbsc_get_arg() == jsxc_get_arg());
+
+            // set once by each handler; the buffer size callback was not cleared in between
+            assert!(jsbsc_get_num_calls() == 2);
+        }
+    }
+
+    #[test]
+    fn test_metadata_handler_failure_clears_all() {
+        let (_co, _gn, _cc, _sc) = (
+            JackClientOpen::setup(),
+            JackGetClientName::setup(),
+            JackClientClose::setup(),
+            JackSetCallbacks::setup());
+
+        let ptr = 0xdeadbeef as *mut jack_sys::jack_client_t;
+        let name = CString::new("test").unwrap();
+        unsafe {
+            jco_set_return(ptr);
+            jgcn_set_return(name.as_ptr());
+        }
+
+        let mut client = Client::open("test", options::NO_START_SERVER).unwrap().0;
+
+        let first = TestMetadataHandler {
+            callbacks: metadata_callbacks::SAMPLE_RATE | metadata_callbacks::BUFFER_SIZE
+        };
+        assert!(client.set_metadata_handler(first).is_ok());
+
+        // the sample rate callback is registered before the xrun callback fails
+        unsafe { jsxc_set_return(-1) };
+        let second = TestMetadataHandler {
+            callbacks: metadata_callbacks::SAMPLE_RATE | metadata_callbacks::XRUN
+        };
+
+        let err = client.set_metadata_handler(second);
+        assert!(err == Err(Error::CallbackRegistrationFailed {
+            client:   "test".to_string(),
+            callback: "xrun",
+            code:     -1,
+        }));
+
+        unsafe {
+            // registered earlier by the failed handler
+            assert!(jssrc_is_set() == 0);
+
+            // registered by the previous handler, which has been dropped
+            assert!(jsbsc_is_set() == 0);
+
+            // the xrun callback was never set, jack refused it
+            assert!(jsxc_is_set() == 0);
+        }
+    }
+
+    #[test]
+    fn test_activate_fail_returns_client() {
+        let (_co, _gn, _cc, _ja) = (
+            JackClientOpen::setup(),
+            JackGetClientName::setup(),
+            JackClientClose::setup(),
+            JackActivate::setup());
+
+        let ptr = 0xdeadbeef as *mut jack_sys::jack_client_t;
+        let name = CString::new("test").unwrap();
+        unsafe {
+            jco_set_return(ptr);
+            jgcn_set_return(name.as_ptr());
+            ja_set_return(-1);
+        }
+
+        let client = Client::open("test", options::NO_START_SERVER).unwrap().0;
+
+        let client = match client.activate() {
+            Ok(_)              => panic!("activation should have failed"),
+            Err((client, err)) => {
+                assert!(err == Error::ActivateFailed { client: "test".to_string(), code: -1 });
+                client
+            },
+        };
+
+        // the client is still open, and can be activated again
+        assert!(unsafe { jcc_get_num_calls() } == 0);
+        unsafe { ja_set_return(0) };
+        let client = client.activate().unwrap();
+        assert!(unsafe { ja_get_num_calls() } == 2);
+
+        drop(client);
+        assert!(unsafe { jcc_get_num_calls() } == 1);
+    }
+
+    #[test]
+    fn test_freewheel_tracked_without_handler() {
+        let (_co, _gn, _cc, _sc) = (
+            JackClientOpen::setup(),
+            JackGetClientName::setup(),
+            JackClientClose::setup(),
+            JackSetCallbacks::setup());
+
+        let ptr = 0xdeadbeef as *mut jack_sys::jack_client_t;
+        unsafe { jco_set_return(ptr) };
+
+        let mut client = Client::open("test", options::NO_START_SERVER).unwrap().0;
+
+        // tracked as soon as the client is open
+        assert!(unsafe { jsfc_is_set() } != 0);
+        unsafe { jsfc_call(1) };
+        assert!(client.inner.freewheeling.load(Ordering::Relaxed));
+
+        let handler = TestMetadataHandler { callbacks: metadata_callbacks::FREEWHEEL };
+        assert!(client.set_metadata_handler(handler).is_ok());
+        unsafe { jsfc_call(0) };
+        assert!(!client.inner.freewheeling.load(Ordering::Relaxed));
+        unsafe { jsfc_call(1) };
+
+        // a handler without FREEWHEEL hands the callback back to the flag instead of clearing it
+        let handler = TestMetadataHandler { callbacks: metadata_callbacks::XRUN };
+        assert!(client.set_metadata_handler(handler).is_ok());
+        assert!(unsafe { jsfc_is_set() } != 0);
+        unsafe { jsfc_call(0) };
+        assert!(!client.inner.freewheeling.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_metadata_handler_leaked_when_clear_fails() {
+        let (_co, _gn, _cc, _sc) = (
+            JackClientOpen::setup(),
+            JackGetClientName::setup(),
+            JackClientClose::setup(),
+            JackSetCallbacks::setup());
+
+        let ptr = 0xdeadbeef as *mut jack_sys::jack_client_t;
+        unsafe { jco_set_return(ptr) };
+
+        let mut client = Client::open("test", options::NO_START_SERVER).unwrap().0;
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let first = DropMetadataHandler {
+            callbacks: metadata_callbacks::SAMPLE_RATE | metadata_callbacks::XRUN,
+            dropped:   dropped.clone(),
+        };
+        assert!(client.set_metadata_handler(first).is_ok());
+        let first_arg = unsafe { jsxc_get_arg() };
+
+        // the second handler doesn't want xruns, but jack refuses to clear the callback
+        unsafe { jsxc_set_return(-1) };
+        let second = TestMetadataHandler { callbacks: metadata_callbacks::SAMPLE_RATE };
+        assert!(client.set_metadata_handler(second).is_ok());
+
+        // jack still points at the first handler, so it must not have been freed
+        unsafe { assert!(jsxc_get_arg() == first_arg) };
+        assert!(!dropped.load(Ordering::Relaxed));
+    }
 }
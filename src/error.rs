@@ -0,0 +1,176 @@
+//! This module defines the error type returned by fallible operations on a client after it has
+//! been opened.
+//!
+//! Failures while opening a client are still reported with the `status::Status` bits provided by
+//! jack, because jack has a lot to say about why a client could not be opened. After the client
+//! is open, jack only gives us a return code (sometimes an errno), so these errors record the
+//! operation which failed, the code, and the names of the client and ports involved.
+
+use std::error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The server refused to activate the client
+    ActivateFailed { client: String, code: i32 },
+
+    /// The server refused to deactivate the client
+    DeactivateFailed { client: String, code: i32 },
+
+    /// The client could not be closed cleanly
+    CloseFailed { client: String, code: i32 },
+
+    /// The server refused to change its buffer size
+    SetBufferSizeFailed { client: String, nframes: u32, code: i32 },
+
+    /// The server refused to enter or leave freewheel mode
+    SetFreewheelFailed { client: String, enabled: bool, code: i32 },
+
+    /// The transport could not be moved to the given frame
+    TransportLocateFailed { client: String, frame: u32, code: i32 },
+
+    /// The transport could not be moved to the given position. `frame` is the frame of the
+    /// requested position
+    TransportRepositionFailed { client: String, frame: u32, code: i32 },
+
+    /// The client could not give up being the timebase master
+    ReleaseTimebaseFailed { client: String, code: i32 },
+
+    /// The sync timeout could not be changed
+    SetSyncTimeoutFailed { client: String, usecs: u64, code: i32 },
+
+    /// A callback could not be registered with the server. `callback` names the callback
+    CallbackRegistrationFailed { client: String, callback: &'static str, code: i32 },
+
+    /// The port could not be registered. jack does not provide an error code in this case
+    PortRegistrationFailed { client: String, port: String, port_type: String },
+
+    /// The port could not be unregistered
+    PortUnregistrationFailed { client: String, port: String, code: i32 },
+
+    /// The ports could not be connected. jack returns `EEXIST` if the ports are already
+    /// connected
+    PortConnectionFailed { client: String, source: String, destination: String, code: i32 },
+
+    /// The ports could not be disconnected
+    PortDisconnectionFailed { client: String, source: String, destination: String, code: i32 },
+
+    /// The alias could not be added to the port
+    SetPortAliasFailed { port: String, alias: String, code: i32 },
+
+    /// The alias could not be removed from the port
+    UnsetPortAliasFailed { port: String, alias: String, code: i32 },
+
+    /// The port could not be renamed
+    PortRenameFailed { port: String, new_name: String, code: i32 },
+
+    /// A ringbuffer holding `size` items could not be allocated
+    RingBufferCreateFailed { size: usize },
+
+    /// The memory of a ringbuffer holding `size` items could not be locked
+    RingBufferLockFailed { size: usize, code: i32 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::ActivateFailed { ref client, code } =>
+                write!(f, "could not activate client {} (jack error code {})", client, code),
+
+            Error::DeactivateFailed { ref client, code } =>
+                write!(f, "could not deactivate client {} (jack error code {})", client, code),
+
+            Error::CloseFailed { ref client, code } =>
+                write!(f, "could not close client {} (jack error code {})", client, code),
+
+            Error::SetBufferSizeFailed { ref client, nframes, code } =>
+                write!(f, "client {} could not set the buffer size to {} (jack error code {})",
+                       client, nframes, code),
+
+            Error::SetFreewheelFailed { ref client, enabled, code } =>
+                write!(f, "client {} could not {} freewheel mode (jack error code {})",
+                       client, if enabled { "enter" } else { "leave" }, code),
+
+            Error::TransportLocateFailed { ref client, frame, code } =>
+                write!(f, "client {} could not locate the transport to frame {} \
+                           (jack error code {})", client, frame, code),
+
+            Error::TransportRepositionFailed { ref client, frame, code } =>
+                write!(f, "client {} could not reposition the transport to frame {} \
+                           (jack error code {})", client, frame, code),
+
+            Error::ReleaseTimebaseFailed { ref client, code } =>
+                write!(f, "client {} could not release the timebase (jack error code {})",
+                       client, code),
+
+            Error::SetSyncTimeoutFailed { ref client, usecs, code } =>
+                write!(f, "client {} could not set the sync timeout to {}us (jack error code {})",
+                       client, usecs, code),
+
+            Error::CallbackRegistrationFailed { ref client, callback, code } =>
+                write!(f, "could not register {} callback for client {} (jack error code {})",
+                       callback, client, code),
+
+            Error::PortRegistrationFailed { ref client, ref port, ref port_type } =>
+                write!(f, "could not register port {} of type \"{}\" for client {}",
+                       port, port_type, client),
+
+            Error::PortUnregistrationFailed { ref client, ref port, code } =>
+                write!(f, "could not unregister port {} of client {} (jack error code {})",
+                       port, client, code),
+
+            Error::PortConnectionFailed { ref client, ref source, ref destination, code } =>
+                write!(f, "client {} could not connect {} to {} (jack error code {})",
+                       client, source, destination, code),
+
+            Error::PortDisconnectionFailed { ref client, ref source, ref destination, code } =>
+                write!(f, "client {} could not disconnect {} from {} (jack error code {})",
+                       client, source, destination, code),
+
+            Error::SetPortAliasFailed { ref port, ref alias, code } =>
+                write!(f, "could not add alias {} to port {} (jack error code {})",
+                       alias, port, code),
+
+            Error::UnsetPortAliasFailed { ref port, ref alias, code } =>
+                write!(f, "could not remove alias {} from port {} (jack error code {})",
+                       alias, port, code),
+
+            Error::PortRenameFailed { ref port, ref new_name, code } =>
+                write!(f, "could not rename port {} to {} (jack error code {})",
+                       port, new_name, code),
+
+            Error::RingBufferCreateFailed { size } =>
+                write!(f, "could not create a ringbuffer holding {} items", size),
+
+            Error::RingBufferLockFailed { size, code } =>
+                write!(f, "could not lock the memory of a ringbuffer holding {} items \
+                           (error code {})", size, code),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::ActivateFailed { .. }             => "could not activate client",
+            Error::DeactivateFailed { .. }           => "could not deactivate client",
+            Error::CloseFailed { .. }                => "could not close client",
+            Error::SetBufferSizeFailed { .. }        => "could not set buffer size",
+            Error::SetFreewheelFailed { .. }         => "could not set freewheel mode",
+            Error::TransportLocateFailed { .. }      => "could not locate transport",
+            Error::TransportRepositionFailed { .. }  => "could not reposition transport",
+            Error::ReleaseTimebaseFailed { .. }      => "could not release timebase",
+            Error::SetSyncTimeoutFailed { .. }       => "could not set sync timeout",
+            Error::CallbackRegistrationFailed { .. } => "could not register callback",
+            Error::PortRegistrationFailed { .. }     => "could not register port",
+            Error::PortUnregistrationFailed { .. }   => "could not unregister port",
+            Error::PortConnectionFailed { .. }       => "could not connect ports",
+            Error::PortDisconnectionFailed { .. }    => "could not disconnect ports",
+            Error::SetPortAliasFailed { .. }         => "could not set port alias",
+            Error::UnsetPortAliasFailed { .. }       => "could not unset port alias",
+            Error::PortRenameFailed { .. }           => "could not rename port",
+            Error::RingBufferCreateFailed { .. }     => "could not create ringbuffer",
+            Error::RingBufferLockFailed { .. }       => "could not lock ringbuffer memory",
+        }
+    }
+}
@@ -2,18 +2,23 @@
 extern crate bitflags;
 extern crate jack_sys;
 extern crate libc;
-extern crate num;
 
 // all the modules
 mod client;
 mod callbackhandler;
+mod error;
 mod port;
+mod ringbuffer;
+mod transport;
 mod types;
 mod midi;
 
 // get everything into this namespace
 pub use callbackhandler::*;
 pub use client::*;
+pub use error::*;
 pub use midi::*;
 pub use port::*;
+pub use ringbuffer::*;
+pub use transport::*;
 pub use types::*;
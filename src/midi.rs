@@ -1,5 +1,7 @@
 use jack_sys;
 use libc;
+use std::error;
+use std::fmt;
 use std::marker::PhantomData;
 use std::mem;
 use std::slice;
@@ -25,30 +27,198 @@ impl<'a> MidiEventBuf<'a> {
         }
     }
 
+    /// Returns the event at `index`, or None if there is no such event.
     /// This looks like it isn't a reference, but it is. Trust me.
-    pub fn get(&self, index: usize) -> MidiEventRef {
+    pub fn get(&self, index: usize) -> Option<MidiEventRef<'a>> {
         assert!(!self.all_events_buffer.is_null());
 
         if index >= self.num {
-            panic!("index out of bounds");
+            return None;
         }
 
         unsafe {
-            let mut jstruct = mem::uninitialized();
+            let mut jstruct = mem::MaybeUninit::<jack_sys::jack_midi_event_t>::uninit();
             let ret = jack_sys::jack_midi_event_get(
-                &mut jstruct,
+                jstruct.as_mut_ptr(),
                 self.all_events_buffer,
                 index as u32);
 
             if ret != 0 {
-                panic!("index out of bounds/ENODATA");
+                // ENODATA, jack could not find the event
+                return None;
             }
 
-            MidiEventRef::new(jstruct)
+            // jack filled in the event
+            Some(MidiEventRef::new(jstruct.assume_init()))
         }
     }
 
     pub fn len(&self) -> usize { self.num }
+
+    pub fn is_empty(&self) -> bool { self.num == 0 }
+
+    /// Returns the number of events which could not be written to this buffer during the cycle,
+    /// usually because it was full. A non-zero count means events have been dropped
+    pub fn lost_event_count(&self) -> u32 {
+        unsafe { jack_sys::jack_midi_get_lost_event_count(self.all_events_buffer) }
+    }
+
+    /// Returns an iterator over the events in the buffer, in order of time
+    pub fn iter<'b>(&'b self) -> MidiEventIter<'a, 'b> {
+        MidiEventIter { buf: self, index: 0 }
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b MidiEventBuf<'a> {
+    type Item = MidiEventRef<'a>;
+    type IntoIter = MidiEventIter<'a, 'b>;
+
+    fn into_iter(self) -> Self::IntoIter { self.iter() }
+}
+
+/// An iterator over the events in a `MidiEventBuf`
+pub struct MidiEventIter<'a: 'b, 'b> {
+    buf: &'b MidiEventBuf<'a>,
+    index: usize,
+}
+
+impl<'a, 'b> Iterator for MidiEventIter<'a, 'b> {
+    type Item = MidiEventRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // skip over any events jack can't find, instead of ending early
+        while self.index < self.buf.len() {
+            let ev = self.buf.get(self.index);
+            self.index += 1;
+
+            if ev.is_some() {
+                return ev;
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.buf.len() - self.index))
+    }
+}
+
+/// Writes midi events into the buffer of an output midi port during the process callback.
+///
+/// Events must be written in order of time, and each time must be less than the number of frames
+/// in the current cycle
+pub struct MidiWriter<'a> {
+    all_events_buffer: *mut libc::c_void,
+    nframes: NumFrames,
+    last_time: NumFrames,
+
+    // only exists to enforce the lifetime
+    phantom: PhantomData<&'a mut libc::c_void>
+}
+
+impl<'a> MidiWriter<'a> {
+    #[doc(hidden)]
+    pub unsafe fn new(jackptr: *mut libc::c_void, nframes: NumFrames) -> Self {
+        assert!(!jackptr.is_null());
+
+        jack_sys::jack_midi_clear_buffer(jackptr);
+
+        MidiWriter {
+            all_events_buffer: jackptr,
+            nframes: nframes,
+            last_time: 0,
+            phantom: PhantomData
+        }
+    }
+
+    /// Removes all of the events written so far in this cycle
+    pub fn clear(&mut self) {
+        unsafe { jack_sys::jack_midi_clear_buffer(self.all_events_buffer) };
+        self.last_time = 0;
+    }
+
+    /// Returns the size of the largest event which can currently be written
+    pub fn max_event_size(&self) -> usize {
+        unsafe { jack_sys::jack_midi_max_event_size(self.all_events_buffer) as usize }
+    }
+
+    /// Writes an event containing the raw midi bytes given at frame `time` of the current cycle
+    pub fn write(&mut self, time: NumFrames, data: &[u8]) -> Result<(), MidiWriteError> {
+        let dest = try!(self.reserve(time, data.len()));
+        dest.copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Encodes and writes a midi message at frame `time` of the current cycle
+    pub fn write_message(&mut self, time: NumFrames, msg: &MidiMessage)
+        -> Result<(), MidiWriteError>
+    {
+        // jack cannot take back a reservation, so check the message before reserving space for it
+        match msg.validate() {
+            Ok(())   => (),
+            Err(err) => return Err(MidiWriteError::InvalidMessage(err)),
+        }
+
+        let dest = try!(self.reserve(time, msg.encoded_len()));
+        msg.encode(dest).unwrap();
+        Ok(())
+    }
+
+    /// Reserves space for an event of `size` bytes at frame `time` of the current cycle, and
+    /// returns it to be filled in. This avoids a copy if the event is built in place
+    pub fn reserve(&mut self, time: NumFrames, size: usize) -> Result<&mut [u8], MidiWriteError> {
+        if time >= self.nframes || time < self.last_time {
+            return Err(MidiWriteError::InvalidTime);
+        }
+
+        let ptr = unsafe {
+            jack_sys::jack_midi_event_reserve(
+                self.all_events_buffer,
+                time,
+                size as libc::size_t)
+        };
+
+        if ptr.is_null() {
+            Err(MidiWriteError::BufferFull)
+        } else {
+            self.last_time = time;
+            Ok(unsafe { slice::from_raw_parts_mut(ptr, size) })
+        }
+    }
+}
+
+/// The reasons writing a midi event can fail. These errors do not allocate, so they can be
+/// handled in the process callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiWriteError {
+    /// There is not enough space left in the port's buffer for the event
+    BufferFull,
+
+    /// The event's time is not in the current cycle, or is before an event already written
+    InvalidTime,
+
+    /// The message could not be encoded
+    InvalidMessage(MidiMessageError),
+}
+
+impl fmt::Display for MidiWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MidiWriteError::InvalidMessage(err) => write!(f, "invalid midi message: {}", err),
+            _ => write!(f, "{}", error::Error::description(self)),
+        }
+    }
+}
+
+impl error::Error for MidiWriteError {
+    fn description(&self) -> &str {
+        match *self {
+            MidiWriteError::BufferFull  => "not enough space in the midi buffer",
+            MidiWriteError::InvalidTime => "midi event time out of order or out of range",
+            MidiWriteError::InvalidMessage(_) => "invalid midi message",
+        }
+    }
 }
 
 /// A structure representing a midi event
@@ -92,8 +262,405 @@ impl<'a> MidiEventRef<'a> {
         }
     }
 
+    /// Decodes the event's midi data. See `MidiMessage::decode`
+    pub fn message(&self) -> Result<MidiMessage<'a>, MidiMessageError> {
+        assert!(!self.buffer.is_null());
+
+        let bytes = unsafe { slice::from_raw_parts(self.buffer, self.len) };
+        MidiMessage::decode(bytes)
+    }
+
     pub fn get_jack_time(&self) -> NumFrames {
         assert!(!self.buffer.is_null());
         self.time
     }
 }
+
+/// A decoded midi message.
+///
+/// Channels are counted from 0 to 15. All other values are 7 bit data bytes (0 to 127), except for
+/// `PitchBend` and `SongPosition`, which carry 14 bit values (0 to 16383).
+///
+/// `NoteOn` with a velocity of 0 is often used in place of `NoteOff`. It is decoded as-is, so
+/// handlers which care should check for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessage<'a> {
+    NoteOff { channel: u8, note: u8, velocity: u8 },
+    NoteOn { channel: u8, note: u8, velocity: u8 },
+    PolyPressure { channel: u8, note: u8, pressure: u8 },
+    ControlChange { channel: u8, controller: u8, value: u8 },
+    ProgramChange { channel: u8, program: u8 },
+    ChannelPressure { channel: u8, pressure: u8 },
+
+    /// `value` is 8192 when the wheel is centered
+    PitchBend { channel: u8, value: u16 },
+
+    /// A system exclusive message. The data does not include the leading 0xF0 or trailing 0xF7
+    SysEx(&'a [u8]),
+
+    // system common messages
+    TimeCodeQuarterFrame(u8),
+    SongPosition(u16),
+    SongSelect(u8),
+    TuneRequest,
+
+    // system realtime messages
+    TimingClock,
+    Start,
+    Continue,
+    Stop,
+    ActiveSensing,
+    Reset,
+}
+
+impl<'a> MidiMessage<'a> {
+    /// Decodes a single, complete midi message. Running status is not supported, because jack
+    /// always delivers the status byte with each event
+    pub fn decode(bytes: &'a [u8]) -> Result<Self, MidiMessageError> {
+        let status = match bytes.first() {
+            Some(&s) if s >= 0x80 => s,
+            Some(_)               => return Err(MidiMessageError::MissingStatus),
+            None                  => return Err(MidiMessageError::Empty),
+        };
+
+        if status == 0xF0 {
+            return MidiMessage::decode_sysex(bytes);
+        }
+
+        let data = &bytes[1..];
+        if data.iter().any(|&b| b >= 0x80) {
+            return Err(MidiMessageError::DataOutOfRange);
+        }
+
+        let expected = try!(MidiMessage::data_len(status));
+        if data.len() != expected {
+            return Err(MidiMessageError::WrongLength);
+        }
+
+        let channel = status & 0x0F;
+        let msg = match status & 0xF0 {
+            0x80 => MidiMessage::NoteOff { channel: channel, note: data[0], velocity: data[1] },
+            0x90 => MidiMessage::NoteOn { channel: channel, note: data[0], velocity: data[1] },
+            0xA0 => MidiMessage::PolyPressure {
+                channel: channel, note: data[0], pressure: data[1]
+            },
+            0xB0 => MidiMessage::ControlChange {
+                channel: channel, controller: data[0], value: data[1]
+            },
+            0xC0 => MidiMessage::ProgramChange { channel: channel, program: data[0] },
+            0xD0 => MidiMessage::ChannelPressure { channel: channel, pressure: data[0] },
+            0xE0 => MidiMessage::PitchBend {
+                channel: channel, value: join_14bit(data[0], data[1])
+            },
+
+            // everything else is a system message
+            _ => match status {
+                0xF1 => MidiMessage::TimeCodeQuarterFrame(data[0]),
+                0xF2 => MidiMessage::SongPosition(join_14bit(data[0], data[1])),
+                0xF3 => MidiMessage::SongSelect(data[0]),
+                0xF6 => MidiMessage::TuneRequest,
+                0xF8 => MidiMessage::TimingClock,
+                0xFA => MidiMessage::Start,
+                0xFB => MidiMessage::Continue,
+                0xFC => MidiMessage::Stop,
+                0xFE => MidiMessage::ActiveSensing,
+                0xFF => MidiMessage::Reset,
+
+                // data_len already rejected the undefined status bytes
+                _ => unreachable!(),
+            }
+        };
+
+        Ok(msg)
+    }
+
+    fn decode_sysex(bytes: &'a [u8]) -> Result<Self, MidiMessageError> {
+        // the caller already checked the leading 0xF0
+        if bytes.len() < 2 || bytes[bytes.len() - 1] != 0xF7 {
+            return Err(MidiMessageError::UnterminatedSysEx);
+        }
+
+        let data = &bytes[1..bytes.len() - 1];
+        if data.iter().any(|&b| b >= 0x80) {
+            return Err(MidiMessageError::DataOutOfRange);
+        }
+
+        Ok(MidiMessage::SysEx(data))
+    }
+
+    /// The number of data bytes which follow the given status byte (for everything but sysex)
+    fn data_len(status: u8) -> Result<usize, MidiMessageError> {
+        match status {
+            0x80..=0xBF | 0xE0..=0xEF => Ok(2),
+            0xC0..=0xDF               => Ok(1),
+            0xF2                      => Ok(2),
+            0xF1 | 0xF3               => Ok(1),
+            0xF6 | 0xF8 | 0xFA..=0xFC | 0xFE | 0xFF => Ok(0),
+            _ => Err(MidiMessageError::UndefinedStatus(status)),
+        }
+    }
+
+    /// Returns the number of bytes needed to encode this message. This only depends on the kind
+    /// of message, so it is also defined for messages with out of range values
+    pub fn encoded_len(&self) -> usize {
+        match *self {
+            MidiMessage::NoteOff { .. }
+            | MidiMessage::NoteOn { .. }
+            | MidiMessage::PolyPressure { .. }
+            | MidiMessage::ControlChange { .. }
+            | MidiMessage::PitchBend { .. }
+            | MidiMessage::SongPosition(_)        => 3,
+
+            MidiMessage::ProgramChange { .. }
+            | MidiMessage::ChannelPressure { .. }
+            | MidiMessage::TimeCodeQuarterFrame(_)
+            | MidiMessage::SongSelect(_)          => 2,
+
+            MidiMessage::SysEx(data)              => data.len() + 2,
+
+            MidiMessage::TuneRequest
+            | MidiMessage::TimingClock
+            | MidiMessage::Start
+            | MidiMessage::Continue
+            | MidiMessage::Stop
+            | MidiMessage::ActiveSensing
+            | MidiMessage::Reset                  => 1,
+        }
+    }
+
+    fn status(&self) -> u8 {
+        match *self {
+            MidiMessage::NoteOff { channel, .. }         => 0x80 | channel,
+            MidiMessage::NoteOn { channel, .. }          => 0x90 | channel,
+            MidiMessage::PolyPressure { channel, .. }    => 0xA0 | channel,
+            MidiMessage::ControlChange { channel, .. }   => 0xB0 | channel,
+            MidiMessage::ProgramChange { channel, .. }   => 0xC0 | channel,
+            MidiMessage::ChannelPressure { channel, .. } => 0xD0 | channel,
+            MidiMessage::PitchBend { channel, .. }       => 0xE0 | channel,
+            MidiMessage::SysEx(_)                        => 0xF0,
+            MidiMessage::TimeCodeQuarterFrame(_)         => 0xF1,
+            MidiMessage::SongPosition(_)                 => 0xF2,
+            MidiMessage::SongSelect(_)                   => 0xF3,
+            MidiMessage::TuneRequest                     => 0xF6,
+            MidiMessage::TimingClock                     => 0xF8,
+            MidiMessage::Start                           => 0xFA,
+            MidiMessage::Continue                        => 0xFB,
+            MidiMessage::Stop                            => 0xFC,
+            MidiMessage::ActiveSensing                   => 0xFE,
+            MidiMessage::Reset                           => 0xFF,
+        }
+    }
+
+    /// Encodes the message into the start of `dest`, and returns the number of bytes written.
+    /// Fails if `dest` is too small, or if any of the message's values are out of range
+    pub fn encode(&self, dest: &mut [u8]) -> Result<usize, MidiMessageError> {
+        try!(self.validate());
+
+        let len = self.encoded_len();
+        if dest.len() < len {
+            return Err(MidiMessageError::BufferTooSmall);
+        }
+
+        dest[0] = self.status();
+        match *self {
+            MidiMessage::NoteOff { note: a, velocity: b, .. }
+                | MidiMessage::NoteOn { note: a, velocity: b, .. }
+                | MidiMessage::PolyPressure { note: a, pressure: b, .. }
+                | MidiMessage::ControlChange { controller: a, value: b, .. } =>
+            {
+                dest[1] = a;
+                dest[2] = b;
+            },
+
+            MidiMessage::ProgramChange { program: a, .. }
+                | MidiMessage::ChannelPressure { pressure: a, .. }
+                | MidiMessage::TimeCodeQuarterFrame(a)
+                | MidiMessage::SongSelect(a) =>
+            {
+                dest[1] = a;
+            },
+
+            MidiMessage::PitchBend { value, .. } | MidiMessage::SongPosition(value) => {
+                dest[1] = (value & 0x7F) as u8;
+                dest[2] = (value >> 7) as u8;
+            },
+
+            MidiMessage::SysEx(data) => {
+                dest[1..len - 1].copy_from_slice(data);
+                dest[len - 1] = 0xF7;
+            },
+
+            _ => (),
+        }
+
+        Ok(len)
+    }
+
+    /// Checks that every value in the message fits in the bits midi gives it
+    fn validate(&self) -> Result<(), MidiMessageError> {
+        let ok = match *self {
+            MidiMessage::NoteOff { channel, note: a, velocity: b }
+                | MidiMessage::NoteOn { channel, note: a, velocity: b }
+                | MidiMessage::PolyPressure { channel, note: a, pressure: b }
+                | MidiMessage::ControlChange { channel, controller: a, value: b } =>
+                channel < 16 && a < 0x80 && b < 0x80,
+
+            MidiMessage::ProgramChange { channel, program: a }
+                | MidiMessage::ChannelPressure { channel, pressure: a } =>
+                channel < 16 && a < 0x80,
+
+            MidiMessage::PitchBend { channel, value } => channel < 16 && value < 0x4000,
+            MidiMessage::SysEx(data)                  => data.iter().all(|&b| b < 0x80),
+            MidiMessage::TimeCodeQuarterFrame(a)      => a < 0x80,
+            MidiMessage::SongPosition(value)          => value < 0x4000,
+            MidiMessage::SongSelect(a)                => a < 0x80,
+            _                                         => true,
+        };
+
+        if ok { Ok(()) } else { Err(MidiMessageError::DataOutOfRange) }
+    }
+}
+
+fn join_14bit(lsb: u8, msb: u8) -> u16 {
+    (lsb as u16) | ((msb as u16) << 7)
+}
+
+/// The reasons a midi message can fail to decode or encode. Like `MidiWriteError`, these do not
+/// allocate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiMessageError {
+    /// There were no bytes to decode
+    Empty,
+
+    /// The first byte is not a status byte
+    MissingStatus,
+
+    /// The status byte is not defined by the midi spec
+    UndefinedStatus(u8),
+
+    /// The message has too many or too few data bytes for its status
+    WrongLength,
+
+    /// A data byte (or a value being encoded) does not fit in the bits midi allows for it
+    DataOutOfRange,
+
+    /// A system exclusive message does not end with 0xF7
+    UnterminatedSysEx,
+
+    /// The buffer is too small to encode the message into
+    BufferTooSmall,
+}
+
+impl fmt::Display for MidiMessageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MidiMessageError::UndefinedStatus(s) =>
+                write!(f, "undefined midi status byte {:#X}", s),
+            _ => write!(f, "{}", error::Error::description(self)),
+        }
+    }
+}
+
+impl error::Error for MidiMessageError {
+    fn description(&self) -> &str {
+        match *self {
+            MidiMessageError::Empty              => "empty midi message",
+            MidiMessageError::MissingStatus      => "midi message has no status byte",
+            MidiMessageError::UndefinedStatus(_) => "undefined midi status byte",
+            MidiMessageError::WrongLength        => "wrong number of data bytes in midi message",
+            MidiMessageError::DataOutOfRange     => "midi data out of range",
+            MidiMessageError::UnterminatedSysEx  => "unterminated sysex message",
+            MidiMessageError::BufferTooSmall     => "buffer too small for midi message",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_channel_messages() {
+        assert_eq!(MidiMessage::decode(&[0x93, 60, 100]),
+                   Ok(MidiMessage::NoteOn { channel: 3, note: 60, velocity: 100 }));
+
+        assert_eq!(MidiMessage::decode(&[0x80, 60, 0]),
+                   Ok(MidiMessage::NoteOff { channel: 0, note: 60, velocity: 0 }));
+
+        assert_eq!(MidiMessage::decode(&[0xCF, 5]),
+                   Ok(MidiMessage::ProgramChange { channel: 15, program: 5 }));
+
+        assert_eq!(MidiMessage::decode(&[0xE0, 0x00, 0x40]),
+                   Ok(MidiMessage::PitchBend { channel: 0, value: 8192 }));
+    }
+
+    #[test]
+    fn test_decode_system_messages() {
+        assert_eq!(MidiMessage::decode(&[0xF0, 1, 2, 3, 0xF7]),
+                   Ok(MidiMessage::SysEx(&[1, 2, 3])));
+
+        assert_eq!(MidiMessage::decode(&[0xF2, 0x7F, 0x7F]), Ok(MidiMessage::SongPosition(0x3FFF)));
+        assert_eq!(MidiMessage::decode(&[0xF8]), Ok(MidiMessage::TimingClock));
+        assert_eq!(MidiMessage::decode(&[0xFF]), Ok(MidiMessage::Reset));
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        assert_eq!(MidiMessage::decode(&[]), Err(MidiMessageError::Empty));
+        assert_eq!(MidiMessage::decode(&[60, 100]), Err(MidiMessageError::MissingStatus));
+        assert_eq!(MidiMessage::decode(&[0x90, 60]), Err(MidiMessageError::WrongLength));
+        assert_eq!(MidiMessage::decode(&[0xF8, 1]), Err(MidiMessageError::WrongLength));
+        assert_eq!(MidiMessage::decode(&[0x90, 60, 0x80]), Err(MidiMessageError::DataOutOfRange));
+        assert_eq!(MidiMessage::decode(&[0xF4]), Err(MidiMessageError::UndefinedStatus(0xF4)));
+        assert_eq!(MidiMessage::decode(&[0xF7]), Err(MidiMessageError::UndefinedStatus(0xF7)));
+        assert_eq!(MidiMessage::decode(&[0xF0, 1, 2]), Err(MidiMessageError::UnterminatedSysEx));
+        assert_eq!(MidiMessage::decode(&[0xF0, 0x90, 0xF7]),
+                   Err(MidiMessageError::DataOutOfRange));
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        let messages = [
+            MidiMessage::NoteOn { channel: 9, note: 36, velocity: 127 },
+            MidiMessage::ControlChange { channel: 1, controller: 7, value: 64 },
+            MidiMessage::ChannelPressure { channel: 2, pressure: 3 },
+            MidiMessage::PitchBend { channel: 0, value: 1234 },
+            MidiMessage::SysEx(&[0x7E, 0x7F, 0x09, 0x01]),
+            MidiMessage::TuneRequest,
+            MidiMessage::Stop,
+        ];
+
+        for msg in messages.iter() {
+            let mut buf = [0; 16];
+            let len = msg.encode(&mut buf).unwrap();
+            assert_eq!(len, msg.encoded_len());
+            assert_eq!(MidiMessage::decode(&buf[..len]), Ok(*msg));
+        }
+    }
+
+    #[test]
+    fn test_encode_invalid() {
+        let mut buf = [0; 3];
+        let msg = MidiMessage::NoteOn { channel: 16, note: 60, velocity: 100 };
+        assert_eq!(msg.encode(&mut buf), Err(MidiMessageError::DataOutOfRange));
+
+        let msg = MidiMessage::PitchBend { channel: 0, value: 0x4000 };
+        assert_eq!(msg.encode(&mut buf), Err(MidiMessageError::DataOutOfRange));
+
+        let msg = MidiMessage::NoteOn { channel: 0, note: 60, velocity: 100 };
+        assert_eq!(msg.encode(&mut buf[..2]), Err(MidiMessageError::BufferTooSmall));
+    }
+
+    #[test]
+    fn test_encoded_len_invalid() {
+        // the length comes from the kind of message, even when its values are out of range
+        let mut buf = [0; 3];
+        let msg = MidiMessage::NoteOn { channel: 0x64, note: 60, velocity: 100 };
+        assert_eq!(msg.encoded_len(), 3);
+        assert_eq!(msg.encode(&mut buf), Err(MidiMessageError::DataOutOfRange));
+
+        let msg = MidiMessage::ProgramChange { channel: 0xFF, program: 0x80 };
+        assert_eq!(msg.encoded_len(), 2);
+        assert_eq!(msg.encode(&mut buf), Err(MidiMessageError::DataOutOfRange));
+    }
+}
@@ -1,14 +1,17 @@
 use jack_sys;
+use libc;
 
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use std::marker::PhantomData;
+use std::ptr;
 use std::slice;
 use std::str::FromStr;
-use num;
 
+use callbackhandler::*;
+use client::*;
+use error::*;
 use midi::*;
 use types::*;
-use callbackhandler::*;
 
 type Jackptr = *mut jack_sys::jack_port_t;
 
@@ -35,13 +38,14 @@ type Jackptr = *mut jack_sys::jack_port_t;
 /// One additional note about Port types.
 /// All of these port types are only handles to underlying ports (think of them as an index into a
 /// vector).
-/// All of these port types implement `Copy`.
+/// Unknown and input port handles implement `Copy`. Output port handles do not, because they hand
+/// out mutable access to the port's buffer; use `as_unknown` to refer to an output port elsewhere.
 /// This means that a Port "handle" may become invalid if the port becomes invalid.
 /// Using a port after it has become invalid is undefined behavior and may cause all sorts of
 /// strange things to occur.
-pub trait Port {
+pub trait Port: sealed::Sealed {
     #[doc(hidden)]
-    fn new(c_port: Jackptr) -> Self;
+    unsafe fn new(c_port: Jackptr) -> Self;
 
     #[doc(hidden)]
     unsafe fn get_raw(&self) -> Jackptr;
@@ -57,7 +61,126 @@ pub trait Port {
         }
     }
 
-    // TODO many other functions
+    /// Returns a handle to the same port which can be copied freely, but which can't be used to
+    /// read or write the port's buffer
+    fn as_unknown(&self) -> UnknownPortHandle {
+        unsafe { UnknownPortHandle::new(self.get_raw()) }
+    }
+
+    /// Gets the port's short name (the part of the name after the colon)
+    fn short_name(&self) -> String {
+        unsafe {
+            let cstr = jack_sys::jack_port_short_name(self.get_raw());
+            String::from(CStr::from_ptr(cstr).to_str().unwrap())
+        }
+    }
+
+    /// Gets the port's type, for instance `port_type::DEFAULT_AUDIO_TYPE`
+    fn port_type(&self) -> String {
+        unsafe {
+            let cstr = jack_sys::jack_port_type(self.get_raw());
+            String::from(CStr::from_ptr(cstr).to_str().unwrap())
+        }
+    }
+
+    /// Gets the port's UUID
+    fn uuid(&self) -> UUID {
+        unsafe { jack_sys::jack_port_uuid(self.get_raw()) }
+    }
+
+    /// Returns true if the port belongs to the given client
+    fn is_mine<C: JackClient>(&self, client: &C) -> bool {
+        unsafe { jack_sys::jack_port_is_mine(client.get_raw(), self.get_raw()) != 0 }
+    }
+
+    /// Returns the number of connections to or from this port
+    fn connected(&self) -> usize {
+        unsafe { jack_sys::jack_port_connected(self.get_raw()) as usize }
+    }
+
+    /// Returns true if this port is connected to the port with the given full name
+    fn connected_to(&self, port_name: &str) -> bool {
+        let cstr = CString::new(port_name).unwrap();
+        unsafe { jack_sys::jack_port_connected_to(self.get_raw(), cstr.as_ptr()) != 0 }
+    }
+
+    /// Returns the full names of the ports this port is connected to. The port does not need to
+    /// belong to `client`
+    ///
+    /// This must not be called from the process callback, or any other callback
+    fn connections<C: JackClient>(&self, client: &C) -> PortNames {
+        unsafe {
+            let names = jack_sys::jack_port_get_all_connections(client.get_raw(), self.get_raw());
+            PortNames::new(names)
+        }
+    }
+
+    /// Returns the port's aliases. A port has at most two aliases
+    fn aliases(&self) -> Vec<String> {
+        unsafe {
+            // jack copies each alias into a buffer we provide, which must be able to hold any
+            // port name
+            let size = jack_sys::jack_port_name_size() as usize;
+            let mut a = vec![0 as libc::c_char; size];
+            let mut b = vec![0 as libc::c_char; size];
+            let mut ptrs = [a.as_mut_ptr(), b.as_mut_ptr()];
+
+            let count = jack_sys::jack_port_get_aliases(self.get_raw(), ptrs.as_mut_ptr());
+
+            ptrs.iter()
+                .take(count as usize)
+                .map(|&p| String::from(CStr::from_ptr(p).to_str().unwrap()))
+                .collect()
+        }
+    }
+
+    /// Adds an alias for the port. jack allows at most two aliases per port
+    fn set_alias(&self, alias: &str) -> Result<(), Error> {
+        let cstr = CString::new(alias).unwrap();
+        let ret = unsafe { jack_sys::jack_port_set_alias(self.get_raw(), cstr.as_ptr()) };
+
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(Error::SetPortAliasFailed {
+                port:  self.get_name(),
+                alias: alias.to_string(),
+                code:  ret,
+            })
+        }
+    }
+
+    /// Removes one of the port's aliases
+    fn unset_alias(&self, alias: &str) -> Result<(), Error> {
+        let cstr = CString::new(alias).unwrap();
+        let ret = unsafe { jack_sys::jack_port_unset_alias(self.get_raw(), cstr.as_ptr()) };
+
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(Error::UnsetPortAliasFailed {
+                port:  self.get_name(),
+                alias: alias.to_string(),
+                code:  ret,
+            })
+        }
+    }
+
+    /// Changes the port's short name. The client part of the full name stays the same
+    fn rename(&self, short_name: &str) -> Result<(), Error> {
+        let cstr = CString::new(short_name).unwrap();
+        let ret = unsafe { jack_sys::jack_port_set_name(self.get_raw(), cstr.as_ptr()) };
+
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(Error::PortRenameFailed {
+                port:     self.get_name(),
+                new_name: short_name.to_string(),
+                code:     ret,
+            })
+        }
+    }
 
     /// Get the flags used to construct this port
     fn get_port_flags(&self) -> port_flags::PortFlags {
@@ -66,56 +189,166 @@ pub trait Port {
     }
 }
 
+/// An iterator over a list of full port names returned by jack. The list is freed when the
+/// iterator is dropped.
+///
+/// Other clients may name their ports with invalid UTF-8, which is replaced with U+FFFD
+pub struct PortNames {
+    names: *mut *const libc::c_char,
+    index: usize,
+}
+
+impl PortNames {
+    #[doc(hidden)]
+    pub unsafe fn new(names: *mut *const libc::c_char) -> Self {
+        PortNames { names: names, index: 0 }
+    }
+}
+
+impl Iterator for PortNames {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        // jack returns NULL instead of an empty list
+        if self.names.is_null() {
+            return None;
+        }
+
+        unsafe {
+            let name = *self.names.offset(self.index as isize);
+            if name.is_null() {
+                None
+            } else {
+                self.index += 1;
+                Some(CStr::from_ptr(name).to_string_lossy().into_owned())
+            }
+        }
+    }
+}
+
+impl Drop for PortNames {
+    fn drop(&mut self) {
+        if !self.names.is_null() {
+            unsafe { jack_sys::jack_free(self.names as *mut libc::c_void) };
+            self.names = ptr::null_mut();
+        }
+    }
+}
+
+mod sealed {
+    /// Keeps other crates from implementing `Port`, `PortKind` and `AutoConnectPort` for types
+    /// jack doesn't know about
+    pub trait Sealed { }
+}
+
+/// The kinds of data a jack port can carry, as sample types for the port handles.
+///
+/// Each kind corresponds to one of jack's port type strings. This trait is sealed, it is only
+/// implemented for `DefaultAudioSample` (audio) and `MidiEvent` (midi)
+pub trait PortKind: sealed::Sealed {
+    /// Returns the jack port type string for ports of this kind
+    fn port_type() -> PortType;
+}
+
+impl sealed::Sealed for DefaultAudioSample { }
+impl PortKind for DefaultAudioSample {
+    fn port_type() -> PortType { port_type::DEFAULT_AUDIO_TYPE }
+}
+
+impl sealed::Sealed for MidiEvent { }
+impl PortKind for MidiEvent {
+    fn port_type() -> PortType { port_type::DEFAULT_MIDI_TYPE }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct UnknownPortHandle {
     c_port: Jackptr
 }
 
 impl UnknownPortHandle {
+    /// Returns true if the port's type string is the one for `SampleType`.
+    /// This doesn't allocate a copy of the type, unlike `Port::port_type`
+    fn has_kind<SampleType: PortKind>(&self) -> bool {
+        unsafe {
+            let cstr = CStr::from_ptr(jack_sys::jack_port_type(self.c_port));
+            cstr.to_bytes() == SampleType::port_type().as_bytes()
+        }
+    }
+
     /// Attempts to coerce the port into an input port
-    /// This function will test the port's flags to ensure that it is actually an input port
-    pub fn as_input<SampleType>(self) -> Option<InputPortHandle<SampleType>> {
+    /// This function will test the port's flags to ensure that it is actually an input port, and
+    /// the port's type to ensure that it carries `SampleType`
+    pub fn as_input<SampleType: PortKind>(self) -> Option<InputPortHandle<SampleType>> {
         let flags = self.get_port_flags();
-        if flags.contains(port_flags::PORT_IS_INPUT) {
-            Some(InputPortHandle::<SampleType>::new(self.c_port))
+        if flags.contains(port_flags::PORT_IS_INPUT) && self.has_kind::<SampleType>() {
+            Some(unsafe { InputPortHandle::<SampleType>::new(self.c_port) })
         } else {
             None
         }
     }
 
     /// Attempts to coerce the port into an output port
-    /// This function will test the port's flags to ensure that it is actually an output port
-    pub fn as_output<SampleType>(self) -> Option<OutputPortHandle<SampleType>> {
+    /// This function will test the port's flags to ensure that it is actually an output port, and
+    /// the port's type to ensure that it carries `SampleType`
+    ///
+    /// This is marked unsafe because the caller must make sure that there is no other
+    /// `OutputPortHandle` for the port. Two handles would hand out aliasing mutable buffers
+    pub unsafe fn as_output<SampleType: PortKind>(self) -> Option<OutputPortHandle<SampleType>> {
         let flags = self.get_port_flags();
-        if flags.contains(port_flags::PORT_IS_OUTPUT) {
+        if flags.contains(port_flags::PORT_IS_OUTPUT) && self.has_kind::<SampleType>() {
             Some(OutputPortHandle::<SampleType>::new(self.c_port))
         } else {
             None
         }
     }
 
+    /// Returns the port's buffer for the current process cycle as raw bytes, for ports whose
+    /// type isn't one of the `PortKind`s. The buffer is `JackClient::port_type_buffer_size`
+    /// bytes long for the port's type.
+    ///
+    /// This is marked unsafe because nothing is known about the layout of the data, and because
+    /// the caller must make sure that nothing else reads or writes the buffer while the slice is
+    /// alive, including another call to this function or a typed handle for the same port
+    #[allow(clippy::mut_from_ref)] // exclusive access is the caller's responsibility, see above
+    pub unsafe fn get_raw_buffer<'a>(&self, nframes: NumFrames, ctx: &'a CallbackContext)
+        -> &'a mut [u8]
+    {
+        // use jack's copy of the type string, so nothing is allocated in the process callback
+        let ptype = jack_sys::jack_port_type(self.c_port);
+        let size = jack_sys::jack_port_type_get_buffer_size(ctx.get_raw(), ptype) as usize;
+
+        let ptr = jack_sys::jack_port_get_buffer(self.c_port, nframes);
+        if ptr.is_null() || size == 0 {
+            &mut []
+        } else {
+            slice::from_raw_parts_mut(ptr as *mut u8, size)
+        }
+    }
+
     /// Forces coercion to an input port
-    /// This is marked unsafe because it DOES NOT check the port flags before coercing it to the
-    /// new type.
+    /// This is marked unsafe because it DOES NOT check the port flags or type before coercing it
+    /// to the new type.
     /// If you are 100% sure your port is an input port, this call can save you some extra
     /// operations. If not, use the safe version!
-    pub unsafe fn force_as_input<SampleType>(self) -> InputPortHandle<SampleType> {
+    pub unsafe fn force_as_input<SampleType: PortKind>(self) -> InputPortHandle<SampleType> {
         InputPortHandle::<SampleType>::new(self.c_port)
     }
 
     /// Forces coercion to an output port
-    /// This is marked unsafe because it DOES NOT check the port flags before coercing it to the
-    /// new type.
+    /// This is marked unsafe because it DOES NOT check the port flags or type before coercing it
+    /// to the new type, and because there must not be any other `OutputPortHandle` for the port
+    /// (see `as_output`).
     /// If you are 100% sure your port is an output port, this call can save you some extra
     /// operations. If not, use the safe version!
-    pub unsafe fn force_as_output<SampleType>(self) -> OutputPortHandle<SampleType> {
+    pub unsafe fn force_as_output<SampleType: PortKind>(self) -> OutputPortHandle<SampleType> {
         OutputPortHandle::<SampleType>::new(self.c_port)
     }
 }
 
+impl sealed::Sealed for UnknownPortHandle { }
 impl Port for UnknownPortHandle {
     #[doc(hidden)]
-    fn new(c_port: Jackptr) -> Self {
+    unsafe fn new(c_port: Jackptr) -> Self {
         UnknownPortHandle { c_port: c_port }
     }
 
@@ -131,7 +364,7 @@ pub struct InputPortHandle<SampleType> {
 
 impl<SampleType> Port for InputPortHandle<SampleType> {
     #[doc(hidden)]
-    fn new(c_port: Jackptr) -> Self {
+    unsafe fn new(c_port: Jackptr) -> Self {
         InputPortHandle {
             c_port: c_port,
             phantom: PhantomData,
@@ -142,14 +375,14 @@ impl<SampleType> Port for InputPortHandle<SampleType> {
     unsafe fn get_raw(&self) -> Jackptr { self.c_port }
 }
 
-impl<SampleType: num::Num> InputPortHandle<SampleType> {
+impl InputPortHandle<DefaultAudioSample> {
     /// Get the input port's readable buffer
     pub fn get_read_buffer<'a>(&self, nframes: NumFrames, _ctx: &'a CallbackContext)
-        -> &'a [SampleType]
+        -> &'a [DefaultAudioSample]
     {
         unsafe {
             let ptr = jack_sys::jack_port_get_buffer(self.c_port, nframes);
-            let ptr = ptr as *mut SampleType;
+            let ptr = ptr as *mut DefaultAudioSample;
             slice::from_raw_parts_mut(ptr, nframes as usize)
         }
     }
@@ -172,7 +405,8 @@ impl InputPortHandle<MidiEvent> {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Not `Copy` or `Clone`: each handle hands out the only mutable access to the port's buffer
+#[derive(Debug)]
 pub struct OutputPortHandle<SampleType> {
     c_port: Jackptr,
     phantom: PhantomData<SampleType>
@@ -180,7 +414,7 @@ pub struct OutputPortHandle<SampleType> {
 
 impl<SampleType> Port for OutputPortHandle<SampleType> {
     #[doc(hidden)]
-    fn new(c_port: Jackptr) -> Self {
+    unsafe fn new(c_port: Jackptr) -> Self {
         OutputPortHandle {
             c_port: c_port,
             phantom: PhantomData,
@@ -191,17 +425,60 @@ impl<SampleType> Port for OutputPortHandle<SampleType> {
     unsafe fn get_raw(&self) -> Jackptr { self.c_port }
 }
 
-impl<SampleType> OutputPortHandle<SampleType> {
-    /// Get the input port's readable buffer
-    pub fn get_write_buffer<'a>(&self, nframes: NumFrames, _ctx: &'a CallbackContext)
-        -> &'a mut [SampleType]
+impl OutputPortHandle<DefaultAudioSample> {
+    /// Get the output port's writable buffer
+    pub fn get_write_buffer<'a>(&'a mut self, nframes: NumFrames, _ctx: &'a CallbackContext)
+        -> &'a mut [DefaultAudioSample]
     {
         unsafe {
             let ptr = jack_sys::jack_port_get_buffer(self.c_port, nframes);
-            let ptr = ptr as *mut SampleType;
+            let ptr = ptr as *mut DefaultAudioSample;
             slice::from_raw_parts_mut(ptr, nframes as usize)
         }
     }
 }
 
+impl OutputPortHandle<MidiEvent> {
+    /// Returns a writer for the port's buffer of midi events.
+    /// The buffer is cleared first, as jack requires before events are written in each cycle.
+    pub fn get_write_buffer<'a>(&'a mut self, nframes: NumFrames, _ctx: &'a CallbackContext)
+        -> MidiWriter<'a>
+    {
+        unsafe {
+            let ptr = jack_sys::jack_port_get_buffer(self.c_port, nframes);
+            MidiWriter::new(ptr, nframes)
+        }
+    }
+}
+
+/// The port handles which `JackClient::auto_connect` can connect to physical ports.
+///
+/// The port decides the direction: output ports are connected to the physical playback ports and
+/// input ports to the physical capture ports. For an `UnknownPortHandle` this is decided by the
+/// port's flags. This trait is sealed, it is only implemented for the port handles in this crate
+pub trait AutoConnectPort: Port {
+    /// Returns the side of the audio interface this port is connected to
+    fn direction(&self) -> Direction;
+}
+
+impl AutoConnectPort for UnknownPortHandle {
+    fn direction(&self) -> Direction {
+        if self.get_port_flags().contains(port_flags::PORT_IS_OUTPUT) {
+            Direction::Playback
+        } else {
+            Direction::Capture
+        }
+    }
+}
+
+impl<SampleType> sealed::Sealed for InputPortHandle<SampleType> { }
+impl<SampleType> AutoConnectPort for InputPortHandle<SampleType> {
+    fn direction(&self) -> Direction { Direction::Capture }
+}
+
+impl<SampleType> sealed::Sealed for OutputPortHandle<SampleType> { }
+impl<SampleType> AutoConnectPort for OutputPortHandle<SampleType> {
+    fn direction(&self) -> Direction { Direction::Playback }
+}
+
 // TODO some nice type aliases to hide all this magic and craziness
@@ -0,0 +1,358 @@
+//! This module wraps jack's lock-free ringbuffer, which is the usual way to move data in and out
+//! of the process callback without locking or allocating.
+//!
+//! The ringbuffer is safe to use with exactly one reader and one writer, so `RingBuffer::new`
+//! splits it into a `Producer` and a `Consumer`. Each half can be sent to a different thread,
+//! neither can be cloned, and the buffer is freed once both halves are dropped.
+
+use jack_sys;
+use libc;
+use std::cmp;
+use std::marker::PhantomData;
+use std::mem;
+use std::slice;
+use std::sync::Arc;
+
+use error::*;
+
+/// Namespace for the constructors of a ringbuffer. See the module documentation for details
+pub struct RingBuffer;
+
+impl RingBuffer {
+    /// Creates a ringbuffer which can hold at least `size` items of type `T`, and returns its two
+    /// halves.
+    ///
+    /// The buffer is allocated here, so this should not be called from the process callback
+    pub fn new<T: Copy>(size: usize) -> Result<(Producer<T>, Consumer<T>), Error> {
+        let raw = try!(RawRingBuffer::create::<T>(size));
+        Ok(RingBuffer::split(raw))
+    }
+
+    /// Creates a ringbuffer like `new`, then locks its memory so that it can never be paged out.
+    /// This avoids page faults when the buffer is accessed from the process callback
+    pub fn new_mlocked<T: Copy>(size: usize) -> Result<(Producer<T>, Consumer<T>), Error> {
+        let raw = try!(RawRingBuffer::create::<T>(size));
+
+        let ret = unsafe { jack_sys::jack_ringbuffer_mlock(raw.ptr) };
+        if ret != 0 {
+            // raw is dropped here, which frees the buffer
+            return Err(Error::RingBufferLockFailed { size: size, code: ret });
+        }
+
+        Ok(RingBuffer::split(raw))
+    }
+
+    fn split<T: Copy>(raw: RawRingBuffer) -> (Producer<T>, Consumer<T>) {
+        let raw = Arc::new(raw);
+        let p = Producer { raw: raw.clone(), phantom: PhantomData };
+        let c = Consumer { raw: raw,         phantom: PhantomData };
+        (p, c)
+    }
+}
+
+/// The owner of the underlying jack ringbuffer, shared by both halves
+struct RawRingBuffer {
+    ptr: *mut jack_sys::jack_ringbuffer_t,
+}
+
+// the halves make sure that only one thread reads and only one thread writes, which is all jack's
+// ringbuffer needs to be safe
+unsafe impl Send for RawRingBuffer { }
+unsafe impl Sync for RawRingBuffer { }
+
+impl RawRingBuffer {
+    fn create<T>(size: usize) -> Result<Self, Error> {
+        // jack keeps one byte of the buffer empty to tell a full buffer from an empty one
+        let bytes = size.checked_mul(mem::size_of::<T>()).and_then(|b| b.checked_add(1));
+        let ptr = match bytes {
+            Some(bytes) => unsafe { jack_sys::jack_ringbuffer_create(bytes as libc::size_t) },
+            None        => ::std::ptr::null_mut(),
+        };
+
+        if ptr.is_null() {
+            Err(Error::RingBufferCreateFailed { size: size })
+        } else {
+            Ok(RawRingBuffer { ptr: ptr })
+        }
+    }
+}
+
+impl Drop for RawRingBuffer {
+    fn drop(&mut self) {
+        unsafe { jack_sys::jack_ringbuffer_free(self.ptr) }
+    }
+}
+
+/// The writing half of a ringbuffer
+pub struct Producer<T: Copy> {
+    raw:     Arc<RawRingBuffer>,
+    phantom: PhantomData<T>,
+}
+
+// T is moved from the producer's thread to the consumer's thread
+unsafe impl<T: Copy + Send> Send for Producer<T> { }
+
+impl<T: Copy> Producer<T> {
+    /// Returns the number of items which can currently be written
+    pub fn write_space(&self) -> usize {
+        let bytes = unsafe { jack_sys::jack_ringbuffer_write_space(self.raw.ptr) };
+        bytes as usize / item_size::<T>()
+    }
+
+    /// Writes a single item. Returns false (and writes nothing) if the buffer is full
+    pub fn push(&mut self, item: T) -> bool {
+        if self.write_space() == 0 {
+            return false;
+        }
+
+        unsafe {
+            let src = &item as *const T as *const libc::c_char;
+            jack_sys::jack_ringbuffer_write(self.raw.ptr, src, mem::size_of::<T>() as libc::size_t);
+        }
+
+        true
+    }
+
+    /// Writes as many items from `items` as will fit. Returns the number of items written
+    pub fn write(&mut self, items: &[T]) -> usize {
+        let count = cmp::min(items.len(), self.write_space());
+
+        unsafe {
+            let src = items.as_ptr() as *const libc::c_char;
+            let bytes = count * mem::size_of::<T>();
+            jack_sys::jack_ringbuffer_write(self.raw.ptr, src, bytes as libc::size_t);
+        }
+
+        count
+    }
+}
+
+impl Producer<u8> {
+    /// Returns the writable part of the buffer, without copying anything. The space may wrap
+    /// around the end of the buffer, in which case it is split into two slices. Either slice may
+    /// be empty.
+    ///
+    /// Nothing written into the slices is visible to the consumer until `write_advance` is called
+    pub fn write_vector(&mut self) -> (&mut [u8], &mut [u8]) {
+        unsafe {
+            let mut vec: [jack_sys::jack_ringbuffer_data_t; 2] = mem::zeroed();
+            jack_sys::jack_ringbuffer_get_write_vector(self.raw.ptr, vec.as_mut_ptr());
+            (raw_slice(&vec[0]), raw_slice(&vec[1]))
+        }
+    }
+
+    /// Makes the next `count` bytes written through `write_vector` visible to the consumer.
+    /// `count` is clamped to the available write space
+    pub fn write_advance(&mut self, count: usize) {
+        let count = cmp::min(count, self.write_space());
+        unsafe { jack_sys::jack_ringbuffer_write_advance(self.raw.ptr, count as libc::size_t) }
+    }
+}
+
+/// The reading half of a ringbuffer
+pub struct Consumer<T: Copy> {
+    raw:     Arc<RawRingBuffer>,
+    phantom: PhantomData<T>,
+}
+
+// T is moved from the producer's thread to the consumer's thread
+unsafe impl<T: Copy + Send> Send for Consumer<T> { }
+
+impl<T: Copy> Consumer<T> {
+    /// Returns the number of items which can currently be read
+    pub fn read_space(&self) -> usize {
+        let bytes = unsafe { jack_sys::jack_ringbuffer_read_space(self.raw.ptr) };
+        bytes as usize / item_size::<T>()
+    }
+
+    /// Reads a single item, or returns None if the buffer is empty
+    pub fn pop(&mut self) -> Option<T> {
+        if self.read_space() == 0 {
+            return None;
+        }
+
+        unsafe {
+            let mut item = mem::MaybeUninit::<T>::uninit();
+            let dest = item.as_mut_ptr() as *mut libc::c_char;
+            jack_sys::jack_ringbuffer_read(self.raw.ptr, dest, mem::size_of::<T>() as libc::size_t);
+
+            // every item in the buffer was written by the producer from a valid T
+            Some(item.assume_init())
+        }
+    }
+
+    /// Reads as many items as are available into `items`. Returns the number of items read
+    pub fn read(&mut self, items: &mut [T]) -> usize {
+        let count = cmp::min(items.len(), self.read_space());
+
+        unsafe {
+            let dest = items.as_mut_ptr() as *mut libc::c_char;
+            let bytes = count * mem::size_of::<T>();
+            jack_sys::jack_ringbuffer_read(self.raw.ptr, dest, bytes as libc::size_t);
+        }
+
+        count
+    }
+}
+
+impl Consumer<u8> {
+    /// Returns the readable part of the buffer, without copying anything. The data may wrap
+    /// around the end of the buffer, in which case it is split into two slices. Either slice may
+    /// be empty.
+    ///
+    /// The data stays in the buffer until `read_advance` is called
+    pub fn read_vector(&self) -> (&[u8], &[u8]) {
+        unsafe {
+            let mut vec: [jack_sys::jack_ringbuffer_data_t; 2] = mem::zeroed();
+            jack_sys::jack_ringbuffer_get_read_vector(self.raw.ptr, vec.as_mut_ptr());
+            (raw_slice(&vec[0]), raw_slice(&vec[1]))
+        }
+    }
+
+    /// Discards the next `count` bytes, making the space available to the producer.
+    /// `count` is clamped to the available read space
+    pub fn read_advance(&mut self, count: usize) {
+        let count = cmp::min(count, self.read_space());
+        unsafe { jack_sys::jack_ringbuffer_read_advance(self.raw.ptr, count as libc::size_t) }
+    }
+}
+
+/// The size of an item in the buffer, avoiding a division by zero for zero sized types
+fn item_size<T>() -> usize {
+    cmp::max(mem::size_of::<T>(), 1)
+}
+
+/// Turns one of jack's ringbuffer vectors into a slice, tied to the lifetime of the caller
+unsafe fn raw_slice<'a>(data: &jack_sys::jack_ringbuffer_data_t) -> &'a mut [u8] {
+    if data.len == 0 {
+        &mut []
+    } else {
+        slice::from_raw_parts_mut(data.buf as *mut u8, data.len as usize)
+    }
+}
+
+// jack's ringbuffer doesn't need a server, so these run against the real thing
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        let (mut p, mut c) = RingBuffer::new::<u32>(4).unwrap();
+        assert!(c.pop().is_none());
+
+        assert!(p.push(1));
+        assert!(p.push(2));
+        assert!(c.read_space() == 2);
+
+        assert!(c.pop() == Some(1));
+        assert!(c.pop() == Some(2));
+        assert!(c.pop().is_none());
+    }
+
+    #[test]
+    fn test_full_and_empty() {
+        let (mut p, mut c) = RingBuffer::new::<u32>(4).unwrap();
+
+        // jack may round the buffer up, but it holds at least what was asked for
+        let capacity = p.write_space();
+        assert!(capacity >= 4);
+
+        for i in 0..capacity {
+            assert!(p.push(i as u32));
+        }
+
+        assert!(p.write_space() == 0);
+        assert!(!p.push(100));
+        assert!(c.read_space() == capacity);
+
+        for i in 0..capacity {
+            assert!(c.pop() == Some(i as u32));
+        }
+
+        assert!(c.read_space() == 0);
+        assert!(p.write_space() == capacity);
+    }
+
+    #[test]
+    fn test_partial_write_read() {
+        let (mut p, mut c) = RingBuffer::new::<u16>(4).unwrap();
+        let capacity = p.write_space();
+
+        // only what fits is written
+        let items: Vec<u16> = (0..capacity as u16 + 3).collect();
+        assert!(p.write(&items) == capacity);
+        assert!(p.write(&items) == 0);
+
+        // reading into a short slice leaves the rest in the buffer
+        let mut out = [0; 2];
+        assert!(c.read(&mut out) == 2);
+        assert!(out == [0, 1]);
+
+        // reading into a long slice only fills what is available
+        let mut out = vec![0; capacity + 3];
+        assert!(c.read(&mut out) == capacity - 2);
+        assert!(out[..capacity - 2] == items[2..capacity]);
+        assert!(c.read(&mut out) == 0);
+    }
+
+    #[test]
+    fn test_vectors_wrap_around() {
+        let (mut p, mut c) = RingBuffer::new::<u8>(7).unwrap();
+        let capacity = p.write_space();
+
+        // move both ends most of the way through the buffer, so the next write wraps around
+        let offset = capacity - 2;
+        let filler = vec![0; offset];
+        assert!(p.write(&filler) == offset);
+        c.read_advance(offset);
+        assert!(c.read_space() == 0);
+
+        let written = {
+            let (a, b) = p.write_vector();
+            assert!(a.len() + b.len() == capacity);
+            assert!(!b.is_empty());
+
+            for (i, byte) in a.iter_mut().chain(b.iter_mut()).enumerate() {
+                *byte = i as u8;
+            }
+
+            a.len() + b.len()
+        };
+
+        // nothing is visible until the write is advanced
+        assert!(c.read_space() == 0);
+        p.write_advance(written);
+        assert!(c.read_space() == capacity);
+
+        {
+            let (a, b) = c.read_vector();
+            assert!(!b.is_empty());
+
+            let read: Vec<u8> = a.iter().chain(b.iter()).cloned().collect();
+            let expected: Vec<u8> = (0..capacity as u8).collect();
+            assert!(read == expected);
+        }
+
+        // advancing is clamped to what is there
+        c.read_advance(capacity + 10);
+        assert!(c.read_space() == 0);
+        p.write_advance(capacity + 10);
+        assert!(c.read_space() == capacity);
+    }
+
+    #[test]
+    fn test_create_overflow() {
+        // size * size_of::<T>() overflows
+        match RingBuffer::new::<u64>(usize::MAX / 4) {
+            Err(Error::RingBufferCreateFailed { size }) => assert!(size == usize::MAX / 4),
+            _ => panic!("expected RingBufferCreateFailed"),
+        }
+
+        // the extra byte jack keeps free overflows
+        match RingBuffer::new::<u8>(usize::MAX) {
+            Err(Error::RingBufferCreateFailed { size }) => assert!(size == usize::MAX),
+            _ => panic!("expected RingBufferCreateFailed"),
+        }
+    }
+}
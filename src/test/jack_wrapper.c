@@ -109,3 +109,143 @@ void jgcn_setup() {
 }
 
 void jgcn_cleanup() { jgcn_setup(); }
+
+/* jack_client_close */
+
+__thread int            jcc_return     = 0;
+__thread jack_client_t* jcc_passed_cl  = NULL;
+__thread size_t         jcc_call_count = 0;
+
+void jcc_set_return(int ret) { jcc_return = ret; }
+jack_client_t* jcc_get_passed_client() { return jcc_passed_cl; }
+size_t jcc_get_num_calls() { return jcc_call_count; }
+
+int jack_client_close(jack_client_t* client) {
+  jcc_call_count += 1;
+  jcc_passed_cl = client;
+  return jcc_return;
+}
+
+void jcc_setup() {
+  jcc_return = 0;
+  jcc_passed_cl = NULL;
+  jcc_call_count = 0;
+}
+
+void jcc_cleanup() { jcc_setup(); }
+
+/* jack_set_sample_rate_callback */
+
+__thread JackSampleRateCallback jssrc_cb  = NULL;
+__thread void*                  jssrc_arg = NULL;
+
+int jssrc_is_set() { return jssrc_cb != NULL; }
+void* jssrc_get_arg() { return jssrc_arg; }
+
+int jack_set_sample_rate_callback(jack_client_t* client, JackSampleRateCallback cb, void* arg) {
+  jssrc_cb  = cb;
+  jssrc_arg = arg;
+  return 0;
+}
+
+void jssrc_setup() {
+  jssrc_cb  = NULL;
+  jssrc_arg = NULL;
+}
+
+void jssrc_cleanup() { jssrc_setup(); }
+
+/* jack_set_buffer_size_callback */
+
+__thread JackBufferSizeCallback jsbsc_cb         = NULL;
+__thread void*                  jsbsc_arg        = NULL;
+__thread size_t                 jsbsc_call_count = 0;
+
+int jsbsc_is_set() { return jsbsc_cb != NULL; }
+void* jsbsc_get_arg() { return jsbsc_arg; }
+size_t jsbsc_get_num_calls() { return jsbsc_call_count; }
+
+int jack_set_buffer_size_callback(jack_client_t* client, JackBufferSizeCallback cb, void* arg) {
+  jsbsc_call_count += 1;
+  jsbsc_cb  = cb;
+  jsbsc_arg = arg;
+  return 0;
+}
+
+void jsbsc_setup() {
+  jsbsc_cb         = NULL;
+  jsbsc_arg        = NULL;
+  jsbsc_call_count = 0;
+}
+
+void jsbsc_cleanup() { jsbsc_setup(); }
+
+/* jack_set_xrun_callback */
+
+__thread int              jsxc_return = 0;
+__thread JackXRunCallback jsxc_cb     = NULL;
+__thread void*            jsxc_arg    = NULL;
+
+void jsxc_set_return(int ret) { jsxc_return = ret; }
+int jsxc_is_set() { return jsxc_cb != NULL; }
+void* jsxc_get_arg() { return jsxc_arg; }
+
+int jack_set_xrun_callback(jack_client_t* client, JackXRunCallback cb, void* arg) {
+  // like jack, leave the old callback in place on failure, whether setting or clearing
+  if (jsxc_return != 0) return jsxc_return;
+
+  jsxc_cb  = cb;
+  jsxc_arg = arg;
+  return 0;
+}
+
+void jsxc_setup() {
+  jsxc_return = 0;
+  jsxc_cb     = NULL;
+  jsxc_arg    = NULL;
+}
+
+void jsxc_cleanup() { jsxc_setup(); }
+
+/* jack_activate */
+
+__thread int    ja_return     = 0;
+__thread size_t ja_call_count = 0;
+
+void ja_set_return(int ret) { ja_return = ret; }
+size_t ja_get_num_calls() { return ja_call_count; }
+
+int jack_activate(jack_client_t* client) {
+  ja_call_count += 1;
+  return ja_return;
+}
+
+void ja_setup() {
+  ja_return     = 0;
+  ja_call_count = 0;
+}
+
+void ja_cleanup() { ja_setup(); }
+
+/* jack_set_freewheel_callback */
+
+__thread JackFreewheelCallback jsfc_cb  = NULL;
+__thread void*                 jsfc_arg = NULL;
+
+int jsfc_is_set() { return jsfc_cb != NULL; }
+
+/* runs the callback the way jack would when entering or leaving freewheel mode */
+void jsfc_call(int starting) { jsfc_cb(starting, jsfc_arg); }
+
+int jack_set_freewheel_callback(jack_client_t* client, JackFreewheelCallback cb, void* arg) {
+  jsfc_cb  = cb;
+  jsfc_arg = arg;
+  return 0;
+}
+
+void jsfc_setup() {
+  jsfc_cb  = NULL;
+  jsfc_arg = NULL;
+}
+
+void jsfc_cleanup() { jsfc_setup(); }
@@ -0,0 +1,266 @@
+//! This module defines safe representations of the jack transport state and position.
+//!
+//! The transport is shared by every client connected to a server. Any client may start, stop or
+//! reposition it, and every client can query where it is.
+
+use jack_sys;
+use std::mem;
+
+use types::*;
+
+/// The state of the jack transport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransportState {
+    /// Transport halted
+    Stopped,
+
+    /// Transport playing
+    Rolling,
+
+    /// For OLD_TRANSPORT, now ignored
+    Looping,
+
+    /// Waiting for sync ready
+    Starting,
+
+    /// Waiting for sync ready on the network
+    NetStarting,
+}
+
+impl TransportState {
+    #[doc(hidden)]
+    pub fn from_raw(state: jack_sys::jack_transport_state_t) -> Self {
+        match state {
+            jack_sys::JackTransportRolling     => TransportState::Rolling,
+            jack_sys::JackTransportLooping     => TransportState::Looping,
+            jack_sys::JackTransportStarting    => TransportState::Starting,
+            jack_sys::JackTransportNetStarting => TransportState::NetStarting,
+
+            // jack does not define any other states
+            _ => TransportState::Stopped,
+        }
+    }
+}
+
+/// Bar, beat and tick information for a transport position, as provided by the timebase master
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BBT {
+    /// current bar, counting from 1
+    pub bar: i32,
+
+    /// current beat within the bar, counting from 1
+    pub beat: i32,
+
+    /// current tick within the beat, counting from 0
+    pub tick: i32,
+
+    /// number of ticks before the start of the current bar
+    pub bar_start_tick: f64,
+
+    /// time signature "numerator"
+    pub beats_per_bar: f32,
+
+    /// time signature "denominator"
+    pub beat_type: f32,
+
+    pub ticks_per_beat: f64,
+
+    pub beats_per_minute: f64,
+}
+
+/// A position of the jack transport
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    /// frame number, always present
+    pub frame: NumFrames,
+
+    /// current frame rate (per second). Set by the server, ignored when repositioning
+    pub frame_rate: NumFrames,
+
+    /// monotonic, free-rolling time in microseconds. Set by the server, ignored when
+    /// repositioning
+    pub usecs: Time,
+
+    /// bar, beat and tick information, if a timebase master provides it
+    pub bbt: Option<BBT>,
+}
+
+impl Position {
+    /// Creates a position at the given frame, without any bar, beat or tick information
+    pub fn at_frame(frame: NumFrames) -> Self {
+        Position {
+            frame:      frame,
+            frame_rate: 0,
+            usecs:      0,
+            bbt:        None,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn from_raw(raw: &jack_sys::jack_position_t) -> Self {
+        let bbt = if raw.valid & jack_sys::JackPositionBBT != 0 {
+            Some(BBT {
+                bar:              raw.bar,
+                beat:             raw.beat,
+                tick:             raw.tick,
+                bar_start_tick:   raw.bar_start_tick,
+                beats_per_bar:    raw.beats_per_bar,
+                beat_type:        raw.beat_type,
+                ticks_per_beat:   raw.ticks_per_beat,
+                beats_per_minute: raw.beats_per_minute,
+            })
+        } else {
+            None
+        };
+
+        Position {
+            frame:      raw.frame,
+            frame_rate: raw.frame_rate,
+            usecs:      raw.usecs,
+            bbt:        bbt,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn to_raw(&self) -> jack_sys::jack_position_t {
+        // everything we don't set (the unique fields, padding, etc) must be zeroed
+        let mut raw: jack_sys::jack_position_t = unsafe { mem::zeroed() };
+        raw.frame      = self.frame;
+        raw.frame_rate = self.frame_rate;
+        raw.usecs      = self.usecs;
+        self.write_bbt(&mut raw);
+        raw
+    }
+
+    /// Writes the bar, beat and tick information (or the lack of it) into a raw position, leaving
+    /// all of the other fields alone
+    #[doc(hidden)]
+    pub fn write_bbt(&self, raw: &mut jack_sys::jack_position_t) {
+        if let Some(bbt) = self.bbt {
+            raw.valid           |= jack_sys::JackPositionBBT;
+            raw.bar              = bbt.bar;
+            raw.beat             = bbt.beat;
+            raw.tick             = bbt.tick;
+            raw.bar_start_tick   = bbt.bar_start_tick;
+            raw.beats_per_bar    = bbt.beats_per_bar;
+            raw.beat_type        = bbt.beat_type;
+            raw.ticks_per_beat   = bbt.ticks_per_beat;
+            raw.beats_per_minute = bbt.beats_per_minute;
+        } else {
+            raw.valid &= !jack_sys::JackPositionBBT;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use jack_sys;
+    use std::mem;
+
+    fn test_bbt() -> BBT {
+        BBT {
+            bar:              3,
+            beat:             2,
+            tick:             480,
+            bar_start_tick:   3840.0,
+            beats_per_bar:    4.0,
+            beat_type:        4.0,
+            ticks_per_beat:   1920.0,
+            beats_per_minute: 120.0,
+        }
+    }
+
+    fn test_position(bbt: Option<BBT>) -> Position {
+        Position {
+            frame:      48000,
+            frame_rate: 44100,
+            usecs:      123456789,
+            bbt:        bbt,
+        }
+    }
+
+    // jack_position_t is packed, so its fields are copied out (with braces) before comparing them
+
+    #[test]
+    fn test_position_round_trip_with_bbt() {
+        let pos = test_position(Some(test_bbt()));
+        let raw = pos.to_raw();
+
+        assert!({ raw.valid } == jack_sys::JackPositionBBT);
+        assert!({ raw.frame } == 48000);
+        assert!({ raw.bar } == 3);
+        assert!({ raw.beats_per_minute } == 120.0);
+
+        assert!(Position::from_raw(&raw) == pos);
+    }
+
+    #[test]
+    fn test_position_round_trip_without_bbt() {
+        let pos = test_position(None);
+        let raw = pos.to_raw();
+
+        assert!({ raw.valid } == 0);
+        assert!({ raw.bar } == 0);
+
+        assert!(Position::from_raw(&raw) == pos);
+    }
+
+    #[test]
+    fn test_from_raw_ignores_bbt_fields_when_not_valid() {
+        let mut raw: jack_sys::jack_position_t = unsafe { mem::zeroed() };
+        raw.frame = 100;
+        raw.bar   = 7;
+        raw.valid = jack_sys::JackPositionTimecode;
+
+        let pos = Position::from_raw(&raw);
+        assert!(pos.frame == 100);
+        assert!(pos.bbt.is_none());
+    }
+
+    fn raw_with_other_fields() -> jack_sys::jack_position_t {
+        let mut raw: jack_sys::jack_position_t = unsafe { mem::zeroed() };
+        raw.frame      = 100;
+        raw.frame_rate = 48000;
+        raw.usecs      = 42;
+        raw.frame_time = 1.5;
+        raw.next_time  = 2.5;
+        raw.bbt_offset = 10;
+        raw.valid      = jack_sys::JackPositionTimecode | jack_sys::JackBBTFrameOffset;
+        raw
+    }
+
+    fn assert_other_fields_untouched(raw: &jack_sys::jack_position_t) {
+        assert!({ raw.frame } == 100);
+        assert!({ raw.frame_rate } == 48000);
+        assert!({ raw.usecs } == 42);
+        assert!({ raw.frame_time } == 1.5);
+        assert!({ raw.next_time } == 2.5);
+        assert!({ raw.bbt_offset } == 10);
+    }
+
+    #[test]
+    fn test_write_bbt_sets_bbt() {
+        let mut raw = raw_with_other_fields();
+        test_position(Some(test_bbt())).write_bbt(&mut raw);
+
+        assert_other_fields_untouched(&raw);
+        assert!({ raw.valid } == jack_sys::JackPositionTimecode
+                                | jack_sys::JackBBTFrameOffset
+                                | jack_sys::JackPositionBBT);
+        assert!(Position::from_raw(&raw).bbt == Some(test_bbt()));
+    }
+
+    #[test]
+    fn test_write_bbt_clears_bbt() {
+        let mut raw = raw_with_other_fields();
+        raw.valid |= jack_sys::JackPositionBBT;
+        raw.bar    = 7;
+
+        test_position(None).write_bbt(&mut raw);
+
+        assert_other_fields_untouched(&raw);
+        assert!({ raw.valid } == jack_sys::JackPositionTimecode | jack_sys::JackBBTFrameOffset);
+        assert!(Position::from_raw(&raw).bbt.is_none());
+    }
+}
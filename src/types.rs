@@ -4,9 +4,21 @@ pub type PortType  = &'static str;
 pub type PortId    = jack_sys::jack_port_id_t;
 pub type NumFrames = jack_sys::jack_nframes_t;
 pub type UUID      = jack_sys::jack_uuid_t;
+pub type Time      = jack_sys::jack_time_t;
 
 pub type DefaultAudioSample = jack_sys::jack_default_audio_sample_t;
 
+/// Which side of the audio interface a client's ports are connected to by
+/// `JackClient::auto_connect`, see `AutoConnectPort`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Connect the client's output ports to the physical playback ports (speakers, etc)
+    Playback,
+
+    /// Connect the physical capture ports (microphones, etc) to the client's input ports
+    Capture,
+}
+
 /// Used by the PortConnectHandler callback function
 pub enum PortConnectStatus {
     PortsConnected,
@@ -57,6 +69,44 @@ pub mod port_flags {
 }
 }
 
+/// This module contains a bitflags! generated struct used by a `MetadataHandler` to select the
+/// callbacks it wishes to be given
+pub mod metadata_callbacks {
+    bitflags! {
+    pub flags MetadataCallbacks: u32 {
+        /// `MetadataHandler::sample_rate_changed`
+        const SAMPLE_RATE = 1 << 0,
+
+        /// `MetadataHandler::buffer_size_changed`
+        const BUFFER_SIZE = 1 << 1,
+
+        /// `MetadataHandler::on_port_connect`
+        const PORT_CONNECT = 1 << 2,
+
+        /// `MetadataHandler::on_client_registration`
+        const CLIENT_REGISTRATION = 1 << 3,
+
+        /// `MetadataHandler::on_port_registration`
+        const PORT_REGISTRATION = 1 << 4,
+
+        /// `MetadataHandler::on_port_rename`
+        const PORT_RENAME = 1 << 5,
+
+        /// `MetadataHandler::graph_order_changed`
+        const GRAPH_ORDER = 1 << 6,
+
+        /// `MetadataHandler::on_xrun`
+        const XRUN = 1 << 7,
+
+        /// `MetadataHandler::freewheel_changed`
+        const FREEWHEEL = 1 << 8,
+
+        /// `MetadataHandler::on_shutdown`
+        const SHUTDOWN = 1 << 9,
+    }
+}
+}
+
 /// This module contains default port type constants
 pub mod port_type {
     // these are #defines in the jack source so jack_sys doesn't pick them up